@@ -0,0 +1,234 @@
+use crate::error::DuneRequestError;
+use crate::parameters::{Parameter, Performance};
+use crate::refresh::RefreshOptions;
+use crate::response::GetResultResponse;
+use chrono::{DateTime, Months, TimeDelta, Utc};
+use std::time::Duration;
+
+/// Length of the windows a backfill range is split into.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Window {
+    Day,
+    Week,
+    /// Calendar month (i.e. windows end on the same day of month as the range starts).
+    Month,
+    Custom(Duration),
+}
+
+impl Window {
+    /// End of the `n`-th (zero based) window of a range starting at `start`.
+    fn end(&self, start: DateTime<Utc>, n: u32) -> DateTime<Utc> {
+        let n = n.saturating_add(1);
+        let end = match self {
+            Window::Day => start.checked_add_signed(TimeDelta::days(n.into())),
+            Window::Week => start.checked_add_signed(TimeDelta::weeks(n.into())),
+            // Counting from `start` keeps its day of month after shorter months.
+            Window::Month => start.checked_add_months(Months::new(n)),
+            Window::Custom(length) => TimeDelta::from_std(length.saturating_mul(n))
+                .ok()
+                .filter(|length| *length > TimeDelta::zero())
+                .and_then(|length| start.checked_add_signed(length)),
+        };
+        end.unwrap_or(DateTime::<Utc>::MAX_UTC)
+    }
+}
+
+/// Splits `[start, end)` into consecutive windows (the last one possibly shorter).
+pub fn windows(
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+    window: Window,
+) -> Vec<(DateTime<Utc>, DateTime<Utc>)> {
+    let mut windows = vec![];
+    let mut window_start = start;
+    while window_start < end {
+        let window_end = window.end(start, windows.len() as u32).min(end);
+        windows.push((window_start, window_end));
+        window_start = window_end;
+    }
+    windows
+}
+
+/// Executes a query taking date bounds as [Parameter::date](Parameter::date)s window by window
+/// (cf. [backfill](crate::client::DuneClient::backfill)).
+///
+/// Windows whose execution times out (or fails due to Dune's time or memory limits)
+/// are split in halves and retried, as long as the halves are no shorter than `min_window`.
+/// Other failures (e.g. SQL errors) would recur for any window and are yielded right away.
+#[derive(Clone, Debug)]
+pub struct Backfill {
+    pub query_id: u32,
+    /// Name of the parameter receiving the (inclusive) start of each window.
+    pub start_parameter: String,
+    /// Name of the parameter receiving the (exclusive) end of each window.
+    pub end_parameter: String,
+    /// Further parameters passed along with every window.
+    pub parameters: Vec<Parameter>,
+    pub window: Window,
+    /// Shortest window resulting from splitting failed windows.
+    pub min_window: Duration,
+    /// Engine size of every window's execution, including the halves of split windows
+    /// (a larger engine may spare splitting windows which hit the memory limit).
+    pub performance: Option<Performance>,
+    /// How every window's execution is awaited. Its `max_wall_time` applies per window:
+    /// a window exceeding it is cancelled and split, each half getting the full
+    /// `max_wall_time` again (so a tight limit trades longer runs for more executions).
+    pub refresh: Option<RefreshOptions>,
+}
+
+impl Backfill {
+    /// Backfill of `query_id` in windows of length `window`, passing their bounds
+    /// as `start_parameter` and `end_parameter` and splitting failed windows down to an hour.
+    pub fn new(query_id: u32, start_parameter: &str, end_parameter: &str, window: Window) -> Self {
+        Backfill {
+            query_id,
+            start_parameter: start_parameter.to_string(),
+            end_parameter: end_parameter.to_string(),
+            parameters: vec![],
+            window,
+            min_window: Duration::from_secs(3600),
+            performance: None,
+            refresh: None,
+        }
+    }
+
+    /// Parameters of the window `[start, end)`.
+    pub(crate) fn parameters(&self, start: DateTime<Utc>, end: DateTime<Utc>) -> Vec<Parameter> {
+        let mut parameters = self.parameters.clone();
+        parameters.push(Parameter::date(&self.start_parameter, start));
+        parameters.push(Parameter::date(&self.end_parameter, end));
+        parameters
+    }
+
+    /// Halves of the window `[start, end)` (None when they would be shorter than `min_window`).
+    pub(crate) fn split(
+        &self,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+    ) -> Option<[(DateTime<Utc>, DateTime<Utc>); 2]> {
+        let half = (end - start) / 2;
+        let min_window = TimeDelta::from_std(self.min_window).ok()?;
+        if half < min_window || half <= TimeDelta::zero() {
+            return None;
+        }
+        let middle = start + half;
+        Some([(start, middle), (middle, end)])
+    }
+}
+
+/// Whether `err` may be avoided by executing a shorter window.
+pub(crate) fn is_splittable(err: &DuneRequestError) -> bool {
+    match err {
+        DuneRequestError::ExecutionFailed {
+            error: Some(error), ..
+        } => error.is_resource_limit(),
+        DuneRequestError::Timeout { .. } => true,
+        _ => false,
+    }
+}
+
+/// Outcome of a single backfill window.
+#[derive(Debug)]
+pub struct WindowResult<T> {
+    pub start: DateTime<Utc>,
+    pub end: DateTime<Utc>,
+    pub result: Result<GetResultResponse<T>, DuneRequestError>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse_utils::dune_date;
+    use crate::response::ExecutionError;
+
+    fn date(value: &str) -> DateTime<Utc> {
+        dune_date(value).unwrap()
+    }
+
+    #[test]
+    fn split_into_windows() {
+        let start = date("2024-01-30 00:00:00");
+        assert_eq!(
+            windows(start, date("2024-02-01 12:00:00"), Window::Day),
+            vec![
+                (start, date("2024-01-31 00:00:00")),
+                (date("2024-01-31 00:00:00"), date("2024-02-01 00:00:00")),
+                (date("2024-02-01 00:00:00"), date("2024-02-01 12:00:00")),
+            ]
+        );
+        assert_eq!(
+            windows(start, date("2024-04-01 00:00:00"), Window::Month),
+            vec![
+                (start, date("2024-02-29 00:00:00")),
+                (date("2024-02-29 00:00:00"), date("2024-03-30 00:00:00")),
+                (date("2024-03-30 00:00:00"), date("2024-04-01 00:00:00")),
+            ]
+        );
+        assert_eq!(
+            windows(start, date("2024-02-14 00:00:00"), Window::Week).len(),
+            3
+        );
+        let six_hours = Window::Custom(Duration::from_secs(6 * 3600));
+        assert_eq!(
+            windows(start, date("2024-01-31 00:00:00"), six_hours).len(),
+            4
+        );
+        assert!(windows(start, start, Window::Day).is_empty());
+    }
+
+    #[test]
+    fn splittable_errors() {
+        let typed = |error_type: &str, message: &str| DuneRequestError::ExecutionFailed {
+            execution_id: "jerb".to_string(),
+            error: Some(ExecutionError {
+                error_type: error_type.to_string(),
+                message: message.to_string(),
+                metadata: None,
+            }),
+        };
+        let failed = |message: &str| typed("FAILED_TYPE_EXECUTION_FAILED", message);
+        assert!(is_splittable(&failed(
+            "Query exceeded per-node memory limit of 40GB"
+        )));
+        assert!(is_splittable(&failed(
+            "Query exceeded maximum time limit of 30.00m"
+        )));
+        assert!(is_splittable(&failed("Query timed out")));
+        assert!(is_splittable(&typed("EXCEEDED_GLOBAL_MEMORY_LIMIT", "")));
+        assert!(!is_splittable(&failed(
+            "line 1:8: Column 'x' cannot be resolved"
+        )));
+        // Identifiers merely resembling limits
+        assert!(!is_splittable(&failed(
+            "line 1:8: Column 'memory_used' cannot be resolved"
+        )));
+        assert!(!is_splittable(&failed(
+            "line 3:5: Function 'timeout_ms' not registered"
+        )));
+        assert!(!is_splittable(&failed(
+            "line 2:1: Column 'exceeded_limit' cannot be resolved"
+        )));
+        assert!(!is_splittable(&DuneRequestError::Cancelled {
+            execution_id: "jerb".to_string()
+        }));
+    }
+
+    #[test]
+    fn split_failed_window() {
+        let backfill = Backfill::new(71, "Start", "End", Window::Day);
+        let (start, end) = (date("2024-01-01 00:00:00"), date("2024-01-02 00:00:00"));
+        let middle = date("2024-01-01 12:00:00");
+        assert_eq!(
+            backfill.split(start, end),
+            Some([(start, middle), (middle, end)])
+        );
+        assert_eq!(backfill.split(start, date("2024-01-01 01:00:00")), None);
+        assert_eq!(
+            backfill.parameters(start, middle),
+            vec![
+                Parameter::date("Start", start),
+                Parameter::date("End", middle)
+            ]
+        );
+    }
+}
@@ -0,0 +1,75 @@
+use crate::error::DuneRequestError;
+use crate::parameters::Performance;
+use crate::refresh::RefreshOptions;
+use crate::response::GetResultResponse;
+use std::time::Duration;
+
+/// Configuration of [run_batch](crate::client::DuneClient::run_batch).
+#[derive(Clone, Debug)]
+pub struct BatchOptions {
+    /// Maximum number of executions running at the same time.
+    pub concurrency: usize,
+    /// Time limit of every single execution, after which it is cancelled
+    /// (i.e. `max_wall_time` of `refresh`, which it overrides). Measured from when Dune
+    /// accepted the submission: retries of the submission and the download of the results
+    /// are not bounded by it.
+    pub job_timeout: Option<Duration>,
+    /// Engine size of all executions (Dune's default when `None`).
+    pub performance: Option<Performance>,
+    /// How every execution is awaited.
+    pub refresh: RefreshOptions,
+}
+
+impl Default for BatchOptions {
+    /// 4 concurrent executions without time limit.
+    fn default() -> Self {
+        BatchOptions {
+            concurrency: 4,
+            job_timeout: None,
+            performance: None,
+            refresh: RefreshOptions::default(),
+        }
+    }
+}
+
+/// Outcome of [run_batch](crate::client::DuneClient::run_batch).
+#[derive(Debug)]
+pub struct BatchResult<T> {
+    /// Result of every job, in the order the jobs were given.
+    pub results: Vec<Result<GetResultResponse<T>, DuneRequestError>>,
+    pub stats: BatchStats,
+}
+
+/// Aggregate statistics of a batch.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct BatchStats {
+    pub succeeded: usize,
+    pub failed: usize,
+    /// Wall time of the whole batch.
+    pub elapsed: Duration,
+    /// Sum of the credits reported for the successful executions.
+    pub credits: f64,
+}
+
+impl<T> BatchResult<T> {
+    /// Collects the `results` of a batch which took `elapsed`.
+    pub(crate) fn new(
+        results: Vec<Result<GetResultResponse<T>, DuneRequestError>>,
+        elapsed: Duration,
+    ) -> Self {
+        let mut stats = BatchStats {
+            elapsed,
+            ..Default::default()
+        };
+        for result in &results {
+            match result {
+                Ok(response) => {
+                    stats.succeeded += 1;
+                    stats.credits += response.execution_cost_credits.unwrap_or_default();
+                }
+                Err(_) => stats.failed += 1,
+            }
+        }
+        BatchResult { results, stats }
+    }
+}
@@ -0,0 +1,57 @@
+use clap::{Parser, Subcommand};
+use duners::{client::DuneClient, sync::SyncPlan};
+use std::env;
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+/// Command line interface to the Dune Analytics API
+/// (authenticated via the `DUNE_API_KEY` environment variable).
+#[derive(Parser)]
+#[command(version, about)]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Creates or updates saved queries from a directory of `.sql` files.
+    Sync {
+        /// Directory containing the `.sql` files.
+        dir: PathBuf,
+        /// Only print the plan without changing anything.
+        #[arg(long)]
+        dry_run: bool,
+    },
+}
+
+#[tokio::main]
+async fn main() -> ExitCode {
+    let cli = Cli::parse();
+    dotenv::dotenv().ok();
+    let Ok(api_key) = env::var("DUNE_API_KEY") else {
+        eprintln!("DUNE_API_KEY is not set (neither in the environment nor in a .env file)");
+        return ExitCode::FAILURE;
+    };
+    let dune = DuneClient::new(&api_key);
+    match cli.command {
+        Command::Sync { dir, dry_run } => {
+            let plan = match SyncPlan::build(&dune, &dir).await {
+                Ok(plan) => plan,
+                Err(err) => {
+                    eprintln!("{err}");
+                    return ExitCode::FAILURE;
+                }
+            };
+            print!("{plan}");
+            if dry_run || plan.is_empty() {
+                return ExitCode::SUCCESS;
+            }
+            if let Err(err) = plan.apply(&dune).await {
+                eprintln!("{err}");
+                return ExitCode::FAILURE;
+            }
+        }
+    }
+    ExitCode::SUCCESS
+}
@@ -0,0 +1,269 @@
+use crate::parameters::{Parameter, Performance};
+use chrono::{DateTime, TimeDelta, Utc};
+use log::{debug, warn};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
+/// How the client interacts with its [ResultCache](ResultCache).
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum CacheMode {
+    /// Serve valid cached results, caching fresh ones.
+    #[default]
+    Use,
+    /// Neither read nor write the cache.
+    Bypass,
+    /// Always fetch (resp. execute) anew, replacing cached results.
+    Refresh,
+}
+
+/// On-disk cache of raw result JSON, avoiding repeated fetches and paid executions
+/// of identical results.
+///
+/// Results of [get_results](crate::client::DuneClient::get_results) are cached per execution,
+/// those of [refresh](crate::client::DuneClient::refresh) per query, parameters and performance tier.
+/// Entries are valid for `ttl` and never beyond the `expires_at` reported by Dune.
+/// Clients sharing a cache directory only see their own entries, as these are kept
+/// in a subdirectory per API location and key.
+///
+/// # Examples
+/// ```
+/// use duners::cache::{CacheMode, ResultCache};
+/// use duners::client::DuneClient;
+/// use std::time::Duration;
+///
+/// let dune = DuneClient::builder("my-api-key")
+///     .cache(ResultCache::new(".dune-cache", Duration::from_secs(24 * 3600)))
+///     .build();
+/// // Force a new execution (updating the cache) for selected calls:
+/// let fresh = dune.with_cache_mode(CacheMode::Refresh);
+/// ```
+#[derive(Clone, Debug)]
+pub struct ResultCache {
+    dir: PathBuf,
+    ttl: Duration,
+    mode: CacheMode,
+}
+
+/// File content of a cache entry.
+#[derive(Serialize, Deserialize)]
+struct CacheEntry {
+    cached_at: DateTime<Utc>,
+    expires_at: Option<DateTime<Utc>>,
+    response: Value,
+}
+
+impl ResultCache {
+    /// Cache storing entries in directory `dir` (created on demand) for `ttl`.
+    pub fn new(dir: impl Into<PathBuf>, ttl: Duration) -> Self {
+        ResultCache {
+            dir: dir.into(),
+            ttl,
+            mode: CacheMode::default(),
+        }
+    }
+
+    /// Same cache, used according to `mode`.
+    pub fn mode(mut self, mode: CacheMode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    /// Same cache, restricted to the entries of the client of `api_key` at `base_url`
+    /// (in a subdirectory named after a fingerprint of both, so the key itself is not stored).
+    pub(crate) fn scoped(mut self, base_url: &str, api_key: &str) -> Self {
+        let client = serde_json::json!([base_url, api_key]).to_string();
+        self.dir = self.dir.join(format!("{:016x}", fnv1a(client.as_bytes())));
+        self
+    }
+
+    /// Key of the results of an execution.
+    pub(crate) fn execution_key(job_id: &str) -> String {
+        let job_id = job_id
+            .chars()
+            .filter(|c| c.is_ascii_alphanumeric())
+            .collect::<String>();
+        format!("execution-{job_id}")
+    }
+
+    /// Key of the results of `query_id`, independent of the order of `parameters`.
+    pub(crate) fn query_key(
+        query_id: u32,
+        parameters: &[Parameter],
+        performance: Option<Performance>,
+    ) -> String {
+        let mut parameters = parameters
+            .iter()
+            .map(|p| (p.key.as_str(), p.value.as_str()))
+            .collect::<Vec<_>>();
+        parameters.sort();
+        // JSON escapes keys and values, so that distinct parameters only share a key
+        // on (astronomically unlikely) collisions of the 64-bit hash.
+        let fingerprint = serde_json::json!([performance, parameters]).to_string();
+        format!("query-{query_id}-{:016x}", fnv1a(fingerprint.as_bytes()))
+    }
+
+    fn path(&self, key: &str) -> PathBuf {
+        self.dir.join(format!("{key}.json"))
+    }
+
+    /// Valid cached response under `key` (always `None` unless in mode [Use](CacheMode::Use)).
+    pub(crate) async fn load(&self, key: &str) -> Option<Value> {
+        if self.mode != CacheMode::Use {
+            return None;
+        }
+        let content = tokio::fs::read(self.path(key)).await.ok()?;
+        let entry = match serde_json::from_slice::<CacheEntry>(&content) {
+            Ok(entry) => entry,
+            Err(err) => {
+                warn!("ignoring corrupt cache entry {key}: {err}");
+                return None;
+            }
+        };
+        let now = Utc::now();
+        let ttl = TimeDelta::from_std(self.ttl).unwrap_or(TimeDelta::MAX);
+        let valid = entry
+            .cached_at
+            .checked_add_signed(ttl)
+            .is_none_or(|end| now < end)
+            && entry.expires_at.is_none_or(|expires_at| now < expires_at);
+        debug!("cache {} for {key}", if valid { "hit" } else { "expired" });
+        valid.then_some(entry.response)
+    }
+
+    /// Caches `response` under `key` (unless in mode [Bypass](CacheMode::Bypass)).
+    /// Failures are logged rather than returned, as caching is best effort.
+    pub(crate) async fn store(&self, key: &str, response: &Value) {
+        if self.mode == CacheMode::Bypass {
+            return;
+        }
+        let expires_at = response
+            .get("expires_at")
+            .and_then(Value::as_str)
+            .and_then(|value| DateTime::parse_from_rfc3339(value).ok())
+            .map(|value| value.with_timezone(&Utc));
+        let entry = CacheEntry {
+            cached_at: Utc::now(),
+            expires_at,
+            response: response.clone(),
+        };
+        let result = async {
+            let content = serde_json::to_vec(&entry)?;
+            self.write(key, &content).await
+        };
+        if let Err(err) = result.await {
+            warn!("failed to cache {key}: {err}");
+        }
+    }
+
+    /// Caches the response cached under `from` also under `to`.
+    pub(crate) async fn copy(&self, from: &str, to: &str) {
+        if self.mode == CacheMode::Bypass {
+            return;
+        }
+        let result = async {
+            let content = tokio::fs::read(self.path(from)).await?;
+            self.write(to, &content).await
+        };
+        if let Err(err) = result.await {
+            warn!("failed to cache {to}: {err}");
+        }
+    }
+
+    /// Replaces the entry under `key` by `content` atomically (via a temporary file),
+    /// so that concurrent readers never see partially written entries.
+    async fn write(&self, key: &str, content: &[u8]) -> std::io::Result<()> {
+        static WRITES: AtomicU64 = AtomicU64::new(0);
+        tokio::fs::create_dir_all(&self.dir).await?;
+        let write = WRITES.fetch_add(1, Ordering::Relaxed);
+        let temp = self
+            .dir
+            .join(format!("{key}.{}-{write}.tmp", std::process::id()));
+        if let Err(err) = tokio::fs::write(&temp, content).await {
+            let _ = tokio::fs::remove_file(&temp).await;
+            return Err(err);
+        }
+        tokio::fs::rename(&temp, self.path(key)).await
+    }
+}
+
+/// 64-bit FNV-1a hash (stable across processes and platforms, unlike `DefaultHasher`).
+fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf29ce484222325, |hash, byte| {
+        (hash ^ u64::from(*byte)).wrapping_mul(0x100000001b3)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn temp_cache(name: &str, ttl: Duration) -> ResultCache {
+        let dir = std::env::temp_dir().join(format!("duners-{name}-{}", std::process::id()));
+        ResultCache::new(dir, ttl)
+    }
+
+    #[test]
+    fn stable_keys() {
+        let params = [Parameter::text("a", "1"), Parameter::number("b", "2")];
+        let reversed = [Parameter::number("b", "2"), Parameter::text("a", "1")];
+        let key = ResultCache::query_key(71, &params, None);
+        assert_eq!(key, ResultCache::query_key(71, &reversed, None));
+        assert_eq!(key, "query-71-93e312b7cf0db020");
+        assert_ne!(
+            key,
+            ResultCache::query_key(71, &params, Some(Performance::Large))
+        );
+        assert_ne!(key, ResultCache::query_key(72, &params, None));
+        assert_ne!(
+            key,
+            ResultCache::query_key(71, &[Parameter::text("a", "1&b=2")], None)
+        );
+        assert_eq!(
+            ResultCache::execution_key("01K9/../QTN"),
+            "execution-01K9QTN"
+        );
+    }
+
+    #[test]
+    fn scoped_per_client() {
+        let cache = ResultCache::new("cache", Duration::ZERO);
+        let scoped = |base_url: &str, api_key: &str| cache.clone().scoped(base_url, api_key).dir;
+        let dir = scoped("https://api.dune.com/api/v1", "key");
+        assert_eq!(dir.parent(), Some(cache.dir.as_path()));
+        assert!(!dir.to_string_lossy().contains("key"));
+        assert_ne!(dir, scoped("https://api.dune.com/api/v1", "other key"));
+        assert_ne!(dir, scoped("http://localhost:8080", "key"));
+    }
+
+    #[tokio::test]
+    async fn load_and_store() {
+        let cache = temp_cache("load-and-store", Duration::from_secs(60));
+        let response = json!({"execution_id": "jerb", "result": {"rows": [1, 2]}});
+        cache.store("jerb", &response).await;
+        assert_eq!(cache.load("jerb").await, Some(response.clone()));
+        assert_eq!(cache.load("other").await, None);
+        assert_eq!(
+            cache.clone().mode(CacheMode::Refresh).load("jerb").await,
+            None
+        );
+
+        cache.copy("jerb", "copy").await;
+        assert_eq!(cache.load("copy").await, Some(response));
+
+        let expired = json!({"expires_at": "2020-01-01T00:00:00Z"});
+        cache.store("expired", &expired).await;
+        assert_eq!(cache.load("expired").await, None);
+
+        let stale = temp_cache("load-and-store", Duration::ZERO);
+        assert_eq!(stale.load("jerb").await, None);
+
+        let bypass = cache.clone().mode(CacheMode::Bypass);
+        bypass.store("bypassed", &json!({})).await;
+        assert_eq!(cache.load("bypassed").await, None);
+        std::fs::remove_dir_all(&cache.dir).unwrap();
+    }
+}
@@ -1,18 +1,39 @@
-use crate::error::{DuneError, DuneRequestError};
-use crate::parameters::Parameter;
+use crate::backfill::{is_splittable, windows, Backfill, WindowResult};
+use crate::batch::{BatchOptions, BatchResult};
+use crate::cache::{CacheMode, ResultCache};
+use crate::error::{parse_retry_after, DuneRequestError};
+use crate::execution::ExecutionHandle;
+use crate::parameters::{Parameter, Performance};
+use crate::query::{CreateQuery, UpdateQuery};
+use crate::rate_limit::RateLimiter;
+use crate::refresh::{ExecutionHistory, InFlight, PollStrategy, RefreshOptions};
 use crate::response::{
-    CancellationResponse, ExecutionResponse, ExecutionStatus, GetResultResponse, GetStatusResponse,
+    CancellationResponse, CreateTableResponse, ExecutionResponse, ExecutionStatus,
+    GetResultResponse, GetStatusResponse, InsertResponse, ListTablesResponse, Query,
+    QueryIdResponse, TableMessageResponse, UploadCsvResponse,
 };
+use crate::retry::RetryPolicy;
+use crate::table::{
+    ndjson_batches, BatchOutcome, CreateTable, TableData, TableRow, UploadCsv,
+    MAX_INSERT_BATCH_BYTES,
+};
+use bytes::Bytes;
+use chrono::{DateTime, Utc};
 use dotenv::dotenv;
+use futures::stream::{self, Stream, StreamExt, TryStreamExt};
 use log::{debug, error, info, warn};
-use reqwest::{Error, Response};
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue, CONTENT_TYPE, RETRY_AFTER};
+use reqwest::{Method, RequestBuilder, Response};
 use serde::de::DeserializeOwned;
-use serde_json::json;
-use std::collections::HashMap;
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::collections::{HashMap, VecDeque};
 use std::env;
-use tokio::time::{sleep, Duration};
+use tokio::time::{sleep, Duration, Instant};
 
 const BASE_URL: &str = "https://api.dune.com/api/v1";
+/// Number of rows requested per page when streaming results.
+pub const DEFAULT_PAGE_SIZE: u32 = 10_000;
 
 /// DuneClient provides an interface for interacting with Dune Analytics API.
 /// Official Documentation here: [https://dune.com/docs/api/](https://dune.com/docs/api/).
@@ -20,90 +41,404 @@ const BASE_URL: &str = "https://api.dune.com/api/v1";
 /// Elementary Routes (i.e. those provided by Dune)
 /// - POST
 ///     - execute_query
+///     - execute_sql
 ///     - cancel_execution
+///     - create_query
+///     - archive_query / unarchive_query
+///     - make_private / make_public
+///     - upload_csv
+///     - create_table
+///     - insert (also from serializable structs via insert_rows)
+///     - clear_table
+/// - PATCH
+///     - update_query
+/// - DELETE
+///     - delete_table
 /// - GET
-///     - get_status
+///     - list_tables
+///     - get_query
+///     - get_status (also as stream of updates via watch)
 ///     - get_results
+///     - get_results_page
+///     - get_latest_results
+///     - get_results_csv (also as raw bytes or byte stream)
+///     - get_latest_results_csv (also as raw bytes)
 ///
 /// Furthermore, this interface also implements a convenience method `refresh` which acts as follows:
 /// 1. Execute query
 /// 2. While execution status is not in a terminal state, sleep and check again
 /// 3. Get and return execution results.
+///
+/// Related conveniences are
+/// - `refresh_sql`: the same as `refresh` for raw SQL,
+/// - `await_execution`: steps 2 and 3 of `refresh` for an existing execution,
+/// - `submit`: step 1 of `refresh`, returning a handle to await or cancel the execution,
+/// - `refresh_if_stale`: only executes when the latest results are older than a given age,
+/// - `run_batch`: refreshes many (parameterized) queries concurrently,
+/// - `backfill`: refreshes a query with date bounds window by window,
+/// - `get_all_results` (resp. `stream_results`): walks all result pages
+///   and concatenates (resp. lazily yields) their rows.
+///
+/// Clients are cheap to clone: the underlying HTTP connection pool is shared between clones.
+#[derive(Clone)]
 pub struct DuneClient {
     /// An essential value for request authentication.
     api_key: String,
+    /// Root of all API routes (without trailing slash).
+    base_url: String,
+    /// HTTP transport reused across all requests.
+    http: reqwest::Client,
+    /// Headers attached to every request (in addition to the API key).
+    headers: HeaderMap,
+    /// When (and how often) failed requests are retried.
+    retry: RetryPolicy,
+    /// Throttles requests client-side (shared between clones).
+    rate_limiter: Option<RateLimiter>,
+    /// Execution times of previously refreshed queries (for adaptive polling).
+    history: ExecutionHistory,
+    /// On-disk cache of results.
+    cache: Option<ResultCache>,
+    /// Refreshes in progress (shared by identical concurrent refreshes).
+    in_flight: InFlight,
+}
+
+/// Builder for [DuneClient](DuneClient) allowing to override the API location
+/// and the HTTP transport (e.g. for proxies, timeouts or a local stand-in server).
+///
+/// # Examples
+/// ```
+/// use duners::client::DuneClientBuilder;
+/// use std::time::Duration;
+///
+/// let http = reqwest::Client::builder()
+///     .timeout(Duration::from_secs(30))
+///     .build()
+///     .unwrap();
+/// let dune = DuneClientBuilder::new("my-api-key")
+///     .base_url("http://localhost:8080/api/v1")
+///     .http_client(http)
+///     .default_header("user-agent", "my-pipeline/1.0")
+///     .build();
+/// ```
+pub struct DuneClientBuilder {
+    api_key: String,
+    base_url: String,
+    http: Option<reqwest::Client>,
+    headers: HeaderMap,
+    retry: RetryPolicy,
+    rate_limiter: Option<RateLimiter>,
+    cache: Option<ResultCache>,
+}
+
+impl DuneClientBuilder {
+    /// Starts a builder with Dune's public API URL and a default HTTP client.
+    pub fn new(api_key: &str) -> Self {
+        DuneClientBuilder {
+            api_key: api_key.to_string(),
+            base_url: BASE_URL.to_string(),
+            http: None,
+            headers: HeaderMap::new(),
+            retry: RetryPolicy::none(),
+            rate_limiter: None,
+            cache: None,
+        }
+    }
+
+    /// Overrides the root URL of all routes (default `https://api.dune.com/api/v1`).
+    pub fn base_url(mut self, base_url: &str) -> Self {
+        self.base_url = base_url.trim_end_matches('/').to_string();
+        self
+    }
+
+    /// Uses a preconfigured `reqwest::Client` (timeouts, proxy, TLS settings, user agent, ...).
+    pub fn http_client(mut self, http: reqwest::Client) -> Self {
+        self.http = Some(http);
+        self
+    }
+
+    /// Adds a header sent along with every request.
+    /// # Panics
+    /// When `name` or `value` are not valid HTTP header components.
+    pub fn default_header(mut self, name: &str, value: &str) -> Self {
+        self.headers.insert(
+            HeaderName::from_bytes(name.as_bytes()).expect("invalid header name"),
+            HeaderValue::from_str(value).expect("invalid header value"),
+        );
+        self
+    }
+
+    /// Replaces all default headers.
+    pub fn default_headers(mut self, headers: HeaderMap) -> Self {
+        self.headers = headers;
+        self
+    }
+
+    /// Retries transient failures (rate limits, server errors, ...) according to `policy`
+    /// (by default, every request is sent exactly once).
+    pub fn retry_policy(mut self, policy: RetryPolicy) -> Self {
+        self.retry = policy;
+        self
+    }
+
+    /// Throttles all requests of the client (and its clones) with `limiter`,
+    /// which may also be shared with other clients.
+    pub fn rate_limiter(mut self, limiter: RateLimiter) -> Self {
+        self.rate_limiter = Some(limiter);
+        self
+    }
+
+    /// Caches results on disk (cf. [ResultCache](ResultCache)).
+    pub fn cache(mut self, cache: ResultCache) -> Self {
+        self.cache = Some(cache);
+        self
+    }
+
+    /// Finalizes the client (falling back on a fresh `reqwest::Client` when none was provided).
+    pub fn build(self) -> DuneClient {
+        let cache = self
+            .cache
+            .map(|cache| cache.scoped(&self.base_url, &self.api_key));
+        DuneClient {
+            api_key: self.api_key,
+            base_url: self.base_url,
+            http: self.http.unwrap_or_default(),
+            headers: self.headers,
+            retry: self.retry,
+            rate_limiter: self.rate_limiter,
+            history: ExecutionHistory::default(),
+            cache,
+            in_flight: InFlight::default(),
+        }
+    }
 }
 
 impl DuneClient {
     /// Constructor
     pub fn new(api_key: &str) -> DuneClient {
-        DuneClient {
-            api_key: api_key.to_string(),
-        }
+        DuneClientBuilder::new(api_key).build()
     }
+
+    /// Constructs a client with the API key read from the `DUNE_API_KEY` environment variable
+    /// (also loading a `.env` file when present).
     pub fn from_env() -> DuneClient {
+        DuneClient::builder_from_env().build()
+    }
+
+    /// Builder starting from the given API key.
+    pub fn builder(api_key: &str) -> DuneClientBuilder {
+        DuneClientBuilder::new(api_key)
+    }
+
+    /// Builder starting from the API key found in the `DUNE_API_KEY` environment variable.
+    pub fn builder_from_env() -> DuneClientBuilder {
         dotenv().ok();
-        DuneClient {
-            api_key: env::var("DUNE_API_KEY").unwrap(),
+        DuneClientBuilder::new(&env::var("DUNE_API_KEY").unwrap())
+    }
+
+    /// Request to `route` carrying the default headers and API key.
+    fn _request(&self, method: Method, route: &str) -> RequestBuilder {
+        self.http
+            .request(method, format!("{}/{route}", self.base_url))
+            .headers(self.headers.clone())
+            .header("x-dune-api-key", &self.api_key)
+    }
+
+    /// Sends `request` (once permitted by the rate limiter, if any),
+    /// retrying transient failures according to the client's retry policy
+    /// (non-idempotent requests only when the policy explicitly allows it).
+    async fn _send(
+        &self,
+        request: RequestBuilder,
+        idempotent: bool,
+    ) -> Result<Response, DuneRequestError> {
+        let mut request = request.build()?;
+        let mut attempts = 1;
+        loop {
+            let retry = request.try_clone();
+            if let Some(limiter) = &self.rate_limiter {
+                limiter.acquire(request.method()).await;
+            }
+            let err = match self.http.execute(request).await {
+                Ok(response) => match DuneClient::_check_status(response).await {
+                    Ok(response) => return Ok(response),
+                    Err(err) => err,
+                },
+                Err(err) => DuneRequestError::from(err),
+            };
+            let delay = self.retry.retry_delay(&err, attempts, idempotent);
+            match (retry, delay) {
+                (Some(retry), Some(delay)) => {
+                    warn!(
+                        "retrying in {:?} (attempt {}/{}) after: {}",
+                        delay,
+                        attempts + 1,
+                        self.retry.max_attempts,
+                        err
+                    );
+                    sleep(delay).await;
+                    request = retry;
+                    attempts += 1;
+                }
+                _ => return Err(err),
+            }
         }
     }
 
-    /// Internal POST request handler
-    async fn _post(&self, route: &str, params: Option<Vec<Parameter>>) -> Result<Response, Error> {
-        let params = params
-            .unwrap_or_default()
-            .into_iter()
-            .map(|p| (p.key, p.value))
-            .collect::<HashMap<_, _>>();
-        let request_url = format!("{BASE_URL}/{route}");
-        debug!("POST to {} with parameters {:?}", route, &params);
-        let client = reqwest::Client::new();
+    /// Clone of the client using its result cache (if any) according to `mode`,
+    /// e.g. to force a new execution with [Refresh](CacheMode::Refresh).
+    pub fn with_cache_mode(&self, mode: CacheMode) -> DuneClient {
+        let mut client = self.clone();
+        client.cache = client.cache.map(|cache| cache.mode(mode));
         client
-            .post(&request_url)
-            .header("x-dune-api-key", &self.api_key)
-            .json(&json!({ "query_parameters": params }))
-            .send()
+    }
+
+    /// Internal POST request handler (for idempotent routes)
+    async fn _post<B: Serialize + ?Sized>(
+        &self,
+        route: &str,
+        body: &B,
+    ) -> Result<Response, DuneRequestError> {
+        debug!("POST to {} with body {}", route, json!(body));
+        self._send(self._request(Method::POST, route).json(body), true)
+            .await
+    }
+
+    /// Internal POST request handler for non-idempotent routes
+    /// (i.e. those creating a new execution, query or rows on every call).
+    async fn _submit<B: Serialize + ?Sized>(
+        &self,
+        route: &str,
+        body: &B,
+    ) -> Result<Response, DuneRequestError> {
+        debug!("POST to {} with body {}", route, json!(body));
+        self._send(self._request(Method::POST, route).json(body), false)
+            .await
+    }
+
+    /// Internal PATCH request handler
+    async fn _patch<B: Serialize + ?Sized>(
+        &self,
+        route: &str,
+        body: &B,
+    ) -> Result<Response, DuneRequestError> {
+        debug!("PATCH to {} with body {}", route, json!(body));
+        self._send(self._request(Method::PATCH, route).json(body), true)
             .await
     }
 
+    /// Internal POST request handler for raw (i.e. non-JSON) bodies, used to insert rows.
+    async fn _post_data(&self, route: &str, data: TableData) -> Result<Response, DuneRequestError> {
+        debug!("POST {} to {}", data.content_type(), route);
+        let request = self
+            ._request(Method::POST, route)
+            .header(CONTENT_TYPE, data.content_type())
+            .body(data.into_body());
+        self._send(request, false).await
+    }
+
+    /// Internal DELETE request handler
+    async fn _delete(&self, route: &str) -> Result<Response, DuneRequestError> {
+        debug!("DELETE {}", route);
+        self._send(self._request(Method::DELETE, route), true).await
+    }
+
     /// Internal GET request handler
-    async fn _get(&self, job_id: &str, command: &str) -> Result<Response, Error> {
-        let request_url = format!("{BASE_URL}/execution/{job_id}/{command}");
-        debug!("GET from {}", &request_url);
-        let client = reqwest::Client::new();
-        client
-            .get(&request_url)
-            .header("x-dune-api-key", &self.api_key)
-            .send()
+    async fn _get(
+        &self,
+        route: &str,
+        query: &[(String, String)],
+    ) -> Result<Response, DuneRequestError> {
+        debug!("GET from {} with query {:?}", route, query);
+        self._send(self._request(Method::GET, route).query(query), true)
             .await
     }
 
     /// Deserializes Responses into appropriate type.
     /// Some "invalid" requests return response JSON, which are parsed and returned as Errors.
     async fn _parse_response<T: DeserializeOwned>(resp: Response) -> Result<T, DuneRequestError> {
-        if resp.status().is_success() {
-            resp.json::<T>().await.map_err(DuneRequestError::from)
-        } else {
-            let err = resp
-                .json::<DuneError>()
-                .await
-                .map_err(DuneRequestError::from)?;
-            error!("request error {:?}", &err);
-            Err(DuneRequestError::from(err))
+        let body = resp.bytes().await.map_err(DuneRequestError::from)?;
+        let deserializer = &mut serde_json::Deserializer::from_slice(&body);
+        serde_path_to_error::deserialize(deserializer).map_err(|err| {
+            let path = err.path().to_string();
+            DuneRequestError::deserialize(&String::from_utf8_lossy(&body), path, err.into_inner())
+        })
+    }
+
+    /// Deserializes (cached) JSON into the appropriate type.
+    fn _from_value<T: DeserializeOwned>(value: &Value) -> Result<T, DuneRequestError> {
+        serde_path_to_error::deserialize(value).map_err(|err| {
+            let path = err.path().to_string();
+            DuneRequestError::deserialize(&value.to_string(), path, err.into_inner())
+        })
+    }
+
+    /// Passes through successful responses and converts unsuccessful ones into errors
+    /// (including the error message of Dune's response JSON).
+    async fn _check_status(resp: Response) -> Result<Response, DuneRequestError> {
+        let status = resp.status();
+        if status.is_success() {
+            return Ok(resp);
         }
+        let retry_after = resp
+            .headers()
+            .get(RETRY_AFTER)
+            .and_then(|value| value.to_str().ok())
+            .and_then(parse_retry_after);
+        let body = resp.text().await.map_err(DuneRequestError::from)?;
+        let err = DuneRequestError::from_status(status, retry_after, &body);
+        error!("request error {:?}", &err);
+        Err(err)
+    }
+
+    /// Deserializes CSV content (with header row) into rows of type `T`.
+    fn _parse_csv<T: DeserializeOwned>(content: &[u8]) -> Result<Vec<T>, DuneRequestError> {
+        csv::Reader::from_reader(content)
+            .deserialize::<T>()
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|err| {
+                let path = match err.position() {
+                    Some(position) => format!("line {}", position.line()),
+                    None => String::new(),
+                };
+                DuneRequestError::deserialize(&String::from_utf8_lossy(content), path, err)
+            })
     }
 
-    /// Execute Query (with or without parameters)
+    /// Execute Query (with or without parameters) on the chosen engine size (Dune's default when `None`).
     /// cf. [https://dune.com/docs/api/api-reference/execute-queries/execute-query-id/](https://dune.com/docs/api/api-reference/execute-queries/execute-query-id/)
     pub async fn execute_query(
         &self,
         query_id: u32,
         params: Option<Vec<Parameter>>,
+        performance: Option<Performance>,
     ) -> Result<ExecutionResponse, DuneRequestError> {
+        let params = params
+            .unwrap_or_default()
+            .into_iter()
+            .map(|p| (p.key, p.value))
+            .collect::<HashMap<_, _>>();
+        let mut body = json!({ "query_parameters": params });
+        if let Some(performance) = performance {
+            body["performance"] = json!(performance);
+        }
         let response = self
-            ._post(&format!("query/{query_id}/execute"), params)
-            .await
-            .map_err(DuneRequestError::from)?;
+            ._submit(&format!("query/{query_id}/execute"), &body)
+            .await?;
+        DuneClient::_parse_response::<ExecutionResponse>(response).await
+    }
+
+    /// Execute raw SQL (without a saved query) on the chosen engine size.
+    /// cf. [https://docs.dune.com/api-reference/executions/endpoint/execute-sql](https://docs.dune.com/api-reference/executions/endpoint/execute-sql)
+    pub async fn execute_sql(
+        &self,
+        sql: &str,
+        performance: Option<Performance>,
+    ) -> Result<ExecutionResponse, DuneRequestError> {
+        let mut body = json!({ "sql": sql });
+        if let Some(performance) = performance {
+            body["performance"] = json!(performance);
+        }
+        let response = self._submit("sql/execute", &body).await?;
         DuneClient::_parse_response::<ExecutionResponse>(response).await
     }
 
@@ -114,19 +449,213 @@ impl DuneClient {
         job_id: &str,
     ) -> Result<CancellationResponse, DuneRequestError> {
         let response = self
-            ._post(&format!("execution/{job_id}/cancel"), None)
-            .await
-            .map_err(DuneRequestError::from)?;
+            ._post(&format!("execution/{job_id}/cancel"), &json!({}))
+            .await?;
         DuneClient::_parse_response::<CancellationResponse>(response).await
     }
 
+    /// Create a saved Query
+    /// cf. [https://docs.dune.com/api-reference/queries/endpoint/create](https://docs.dune.com/api-reference/queries/endpoint/create)
+    pub async fn create_query(
+        &self,
+        query: &CreateQuery,
+    ) -> Result<QueryIdResponse, DuneRequestError> {
+        let response = self._submit("query", query).await?;
+        DuneClient::_parse_response::<QueryIdResponse>(response).await
+    }
+
+    /// Read a saved Query (SQL text, name, tags and parameter definitions)
+    /// cf. [https://docs.dune.com/api-reference/queries/endpoint/read](https://docs.dune.com/api-reference/queries/endpoint/read)
+    pub async fn get_query(&self, query_id: u32) -> Result<Query, DuneRequestError> {
+        let response = self._get(&format!("query/{query_id}"), &[]).await?;
+        DuneClient::_parse_response::<Query>(response).await
+    }
+
+    /// Update a saved Query (fields left as `None` remain unchanged)
+    /// cf. [https://docs.dune.com/api-reference/queries/endpoint/update](https://docs.dune.com/api-reference/queries/endpoint/update)
+    pub async fn update_query(
+        &self,
+        query_id: u32,
+        update: &UpdateQuery,
+    ) -> Result<QueryIdResponse, DuneRequestError> {
+        let response = self._patch(&format!("query/{query_id}"), update).await?;
+        DuneClient::_parse_response::<QueryIdResponse>(response).await
+    }
+
+    /// Archive a saved Query
+    /// cf. [https://docs.dune.com/api-reference/queries/endpoint/archive](https://docs.dune.com/api-reference/queries/endpoint/archive)
+    pub async fn archive_query(&self, query_id: u32) -> Result<QueryIdResponse, DuneRequestError> {
+        self._query_command(query_id, "archive").await
+    }
+
+    /// Unarchive a saved Query
+    /// cf. [https://docs.dune.com/api-reference/queries/endpoint/unarchive](https://docs.dune.com/api-reference/queries/endpoint/unarchive)
+    pub async fn unarchive_query(
+        &self,
+        query_id: u32,
+    ) -> Result<QueryIdResponse, DuneRequestError> {
+        self._query_command(query_id, "unarchive").await
+    }
+
+    /// Make a saved Query private
+    /// cf. [https://docs.dune.com/api-reference/queries/endpoint/private](https://docs.dune.com/api-reference/queries/endpoint/private)
+    pub async fn make_private(&self, query_id: u32) -> Result<QueryIdResponse, DuneRequestError> {
+        self._query_command(query_id, "private").await
+    }
+
+    /// Make a private Query public again
+    /// cf. [https://docs.dune.com/api-reference/queries/endpoint/unprivate](https://docs.dune.com/api-reference/queries/endpoint/unprivate)
+    pub async fn make_public(&self, query_id: u32) -> Result<QueryIdResponse, DuneRequestError> {
+        self._query_command(query_id, "unprivate").await
+    }
+
+    /// Internal handler for the body-less POST routes of saved queries.
+    async fn _query_command(
+        &self,
+        query_id: u32,
+        command: &str,
+    ) -> Result<QueryIdResponse, DuneRequestError> {
+        let response = self
+            ._post(&format!("query/{query_id}/{command}"), &json!({}))
+            .await?;
+        DuneClient::_parse_response::<QueryIdResponse>(response).await
+    }
+
+    /// Upload CSV content as a (new or replaced) table
+    /// cf. [https://docs.dune.com/api-reference/tables/endpoint/upload](https://docs.dune.com/api-reference/tables/endpoint/upload)
+    pub async fn upload_csv(
+        &self,
+        upload: &UploadCsv,
+    ) -> Result<UploadCsvResponse, DuneRequestError> {
+        let response = self._post("table/upload/csv", upload).await?;
+        DuneClient::_parse_response::<UploadCsvResponse>(response).await
+    }
+
+    /// Create an empty table with the given schema
+    /// cf. [https://docs.dune.com/api-reference/tables/endpoint/create](https://docs.dune.com/api-reference/tables/endpoint/create)
+    pub async fn create_table(
+        &self,
+        table: &CreateTable,
+    ) -> Result<CreateTableResponse, DuneRequestError> {
+        let response = self._post("table/create", table).await?;
+        DuneClient::_parse_response::<CreateTableResponse>(response).await
+    }
+
+    /// Insert CSV or NDJSON rows into an existing table
+    /// cf. [https://docs.dune.com/api-reference/tables/endpoint/insert](https://docs.dune.com/api-reference/tables/endpoint/insert)
+    pub async fn insert(
+        &self,
+        namespace: &str,
+        table_name: &str,
+        data: TableData,
+    ) -> Result<InsertResponse, DuneRequestError> {
+        let response = self
+            ._post_data(&format!("table/{namespace}/{table_name}/insert"), data)
+            .await?;
+        DuneClient::_parse_response::<InsertResponse>(response).await
+    }
+
+    /// Insert serializable `rows` into a table, creating it (with schema `T::schema()`) when missing.
+    /// Rows are sent as NDJSON in batches of at most [MAX_INSERT_BATCH_BYTES](MAX_INSERT_BATCH_BYTES),
+    /// and the outcome of each batch is reported (failed batches do not stop subsequent ones).
+    pub async fn insert_rows<T: Serialize + TableRow>(
+        &self,
+        namespace: &str,
+        table_name: &str,
+        rows: &[T],
+    ) -> Result<Vec<BatchOutcome>, DuneRequestError> {
+        self.insert_rows_batched(namespace, table_name, rows, MAX_INSERT_BATCH_BYTES)
+            .await
+    }
+
+    /// Same as [insert_rows](DuneClient::insert_rows) with custom upper bound on batch sizes.
+    pub async fn insert_rows_batched<T: Serialize + TableRow>(
+        &self,
+        namespace: &str,
+        table_name: &str,
+        rows: &[T],
+        max_batch_bytes: usize,
+    ) -> Result<Vec<BatchOutcome>, DuneRequestError> {
+        let batches = ndjson_batches(rows, max_batch_bytes)?;
+        let table = self
+            .create_table(&CreateTable {
+                namespace: namespace.to_string(),
+                table_name: table_name.to_string(),
+                schema: T::schema(),
+                ..Default::default()
+            })
+            .await?;
+        if !table.already_existed {
+            info!("created table {}", table.full_name);
+        }
+        let mut outcomes = vec![];
+        for batch in batches {
+            let bytes = batch.body.len();
+            let result = self
+                .insert(namespace, table_name, TableData::NdJson(batch.body))
+                .await;
+            if let Err(err) = &result {
+                warn!(
+                    "failed to insert {} rows into {}: {:?}",
+                    batch.rows, table.full_name, err
+                );
+            }
+            outcomes.push(BatchOutcome {
+                rows: batch.rows,
+                bytes,
+                result,
+            });
+        }
+        Ok(outcomes)
+    }
+
+    /// Remove all rows of a table (keeping its schema)
+    /// cf. [https://docs.dune.com/api-reference/tables/endpoint/clear](https://docs.dune.com/api-reference/tables/endpoint/clear)
+    pub async fn clear_table(
+        &self,
+        namespace: &str,
+        table_name: &str,
+    ) -> Result<TableMessageResponse, DuneRequestError> {
+        let response = self
+            ._post(&format!("table/{namespace}/{table_name}/clear"), &json!({}))
+            .await?;
+        DuneClient::_parse_response::<TableMessageResponse>(response).await
+    }
+
+    /// Delete a table
+    /// cf. [https://docs.dune.com/api-reference/tables/endpoint/delete](https://docs.dune.com/api-reference/tables/endpoint/delete)
+    pub async fn delete_table(
+        &self,
+        namespace: &str,
+        table_name: &str,
+    ) -> Result<TableMessageResponse, DuneRequestError> {
+        let response = self
+            ._delete(&format!("table/{namespace}/{table_name}"))
+            .await?;
+        DuneClient::_parse_response::<TableMessageResponse>(response).await
+    }
+
+    /// List tables owned by the API key's account (one page of at most `limit` tables)
+    /// cf. [https://docs.dune.com/api-reference/tables/endpoint/list](https://docs.dune.com/api-reference/tables/endpoint/list)
+    pub async fn list_tables(
+        &self,
+        limit: u32,
+        offset: u64,
+    ) -> Result<ListTablesResponse, DuneRequestError> {
+        let query = [
+            ("limit".to_string(), limit.to_string()),
+            ("offset".to_string(), offset.to_string()),
+        ];
+        let response = self._get("tables", &query).await?;
+        DuneClient::_parse_response::<ListTablesResponse>(response).await
+    }
+
     /// Get Query Execution Status (by `job_id`)
     /// cf. [https://dune.com/docs/api/api-reference/get-results/execution-status/](https://dune.com/docs/api/api-reference/get-results/execution-status/)
     pub async fn get_status(&self, job_id: &str) -> Result<GetStatusResponse, DuneRequestError> {
         let response = self
-            ._get(job_id, "status")
-            .await
-            .map_err(DuneRequestError::from)?;
+            ._get(&format!("execution/{job_id}/status"), &[])
+            .await?;
         DuneClient::_parse_response::<GetStatusResponse>(response).await
     }
 
@@ -136,25 +665,347 @@ impl DuneClient {
         &self,
         job_id: &str,
     ) -> Result<GetResultResponse<T>, DuneRequestError> {
+        if self.cache.is_none() {
+            let response = self
+                ._get(&format!("execution/{job_id}/results"), &[])
+                .await?;
+            return DuneClient::_parse_response::<GetResultResponse<T>>(response).await;
+        }
+        let value = self._get_results_json(job_id).await?;
+        DuneClient::_from_value(&value)
+    }
+
+    /// Raw JSON results of execution `job_id` (served from and stored in the cache, if any).
+    async fn _get_results_json(&self, job_id: &str) -> Result<Value, DuneRequestError> {
+        let route = format!("execution/{job_id}/results");
+        let Some(cache) = &self.cache else {
+            let response = self._get(&route, &[]).await?;
+            return DuneClient::_parse_response::<Value>(response).await;
+        };
+        let key = ResultCache::execution_key(job_id);
+        let value = match cache.load(&key).await {
+            Some(value) => value,
+            None => {
+                let response = self._get(&route, &[]).await?;
+                let value = DuneClient::_parse_response::<Value>(response).await?;
+                // Results of unfinished (or unsuccessful) executions are incomplete.
+                let completed = value
+                    .get("state")
+                    .and_then(Value::as_str)
+                    .is_some_and(|state| {
+                        let Ok(state) = state.parse::<ExecutionStatus>();
+                        matches!(
+                            state,
+                            ExecutionStatus::Complete | ExecutionStatus::CompletedPartial
+                        )
+                    });
+                if completed {
+                    cache.store(&key, &value).await;
+                }
+                value
+            }
+        };
+        Ok(value)
+    }
+
+    /// Get a single page of Query Execution Results (by `job_id`).
+    /// The `next_offset` of the returned page (if any) is where the following page starts.
+    /// cf. [https://docs.dune.com/api-reference/executions/pagination](https://docs.dune.com/api-reference/executions/pagination)
+    pub async fn get_results_page<T: DeserializeOwned>(
+        &self,
+        job_id: &str,
+        offset: u64,
+        limit: u32,
+    ) -> Result<GetResultResponse<T>, DuneRequestError> {
+        let query = [
+            ("offset".to_string(), offset.to_string()),
+            ("limit".to_string(), limit.to_string()),
+        ];
         let response = self
-            ._get(job_id, "results")
-            .await
-            .map_err(DuneRequestError::from)?;
+            ._get(&format!("execution/{job_id}/results"), &query)
+            .await?;
+        let page = DuneClient::_parse_response::<GetResultResponse<T>>(response).await?;
+        if let Some(row_count) = page.result.metadata.row_count {
+            if row_count as usize != page.result.rows.len() {
+                return Err(DuneRequestError::UnexpectedRowCount {
+                    expected: row_count as u64,
+                    received: page.result.rows.len() as u64,
+                });
+            }
+        }
+        Ok(page)
+    }
+
+    /// Walks all result pages of size `page_size` (by `job_id`)
+    /// and concatenates them into a single response.
+    /// The combined number of rows is validated against the reported `total_row_count`.
+    pub async fn get_all_results<T: DeserializeOwned>(
+        &self,
+        job_id: &str,
+        page_size: u32,
+    ) -> Result<GetResultResponse<T>, DuneRequestError> {
+        let mut response = self.get_results_page::<T>(job_id, 0, page_size).await?;
+        let mut offset = 0;
+        while let Some(next_offset) = response.next_offset.take() {
+            if next_offset <= offset {
+                break;
+            }
+            offset = next_offset;
+            debug!("fetching results page at offset {offset} for {job_id}");
+            let mut page = self
+                .get_results_page::<T>(job_id, offset, page_size)
+                .await?;
+            response.result.rows.append(&mut page.result.rows);
+            response.next_offset = page.next_offset;
+        }
+        response.next_uri = None;
+        let total = response.result.metadata.total_row_count as u64;
+        let received = response.result.rows.len() as u64;
+        if total != received {
+            return Err(DuneRequestError::UnexpectedRowCount {
+                expected: total,
+                received,
+            });
+        }
+        response.result.metadata.row_count = Some(response.result.metadata.total_row_count);
+        Ok(response)
+    }
+
+    /// Get the latest Results of a Query (by `query_id`) without triggering a new execution.
+    /// When `parameters` are given, the latest results for that parameter combination are returned.
+    /// cf. [https://docs.dune.com/api-reference/executions/endpoint/get-query-result](https://docs.dune.com/api-reference/executions/endpoint/get-query-result)
+    pub async fn get_latest_results<T: DeserializeOwned>(
+        &self,
+        query_id: u32,
+        parameters: Option<Vec<Parameter>>,
+    ) -> Result<GetResultResponse<T>, DuneRequestError> {
+        let query = parameters
+            .unwrap_or_default()
+            .into_iter()
+            .map(|p| (format!("params.{}", p.key), p.value))
+            .collect::<Vec<_>>();
+        let response = self
+            ._get(&format!("query/{query_id}/results"), &query)
+            .await?;
         DuneClient::_parse_response::<GetResultResponse<T>>(response).await
     }
 
+    /// Get Query Execution Results (by `job_id`) as raw CSV (including header row).
+    /// cf. [https://docs.dune.com/api-reference/executions/endpoint/get-execution-result-csv](https://docs.dune.com/api-reference/executions/endpoint/get-execution-result-csv)
+    pub async fn get_results_csv_bytes(&self, job_id: &str) -> Result<Bytes, DuneRequestError> {
+        self._get(&format!("execution/{job_id}/results/csv"), &[])
+            .await?
+            .bytes()
+            .await
+            .map_err(DuneRequestError::from)
+    }
+
+    /// Same as [get_results_csv_bytes](DuneClient::get_results_csv_bytes),
+    /// but yields the CSV body in chunks as they arrive.
+    pub async fn get_results_csv_stream(
+        &self,
+        job_id: &str,
+    ) -> Result<impl Stream<Item = Result<Bytes, DuneRequestError>>, DuneRequestError> {
+        let response = self
+            ._get(&format!("execution/{job_id}/results/csv"), &[])
+            .await?;
+        Ok(response.bytes_stream().map_err(DuneRequestError::from))
+    }
+
+    /// Get Query Execution Results (by `job_id`) via the CSV route,
+    /// deserializing rows into the same types used with [get_results](DuneClient::get_results).
+    pub async fn get_results_csv<T: DeserializeOwned>(
+        &self,
+        job_id: &str,
+    ) -> Result<Vec<T>, DuneRequestError> {
+        let content = self.get_results_csv_bytes(job_id).await?;
+        DuneClient::_parse_csv(&content)
+    }
+
+    /// Get the latest Results of a Query (by `query_id`) as raw CSV (including header row).
+    /// cf. [https://docs.dune.com/api-reference/executions/endpoint/get-query-result-csv](https://docs.dune.com/api-reference/executions/endpoint/get-query-result-csv)
+    pub async fn get_latest_results_csv_bytes(
+        &self,
+        query_id: u32,
+    ) -> Result<Bytes, DuneRequestError> {
+        self._get(&format!("query/{query_id}/results/csv"), &[])
+            .await?
+            .bytes()
+            .await
+            .map_err(DuneRequestError::from)
+    }
+
+    /// Get the latest Results of a Query (by `query_id`) via the CSV route,
+    /// deserializing rows into type `T`.
+    pub async fn get_latest_results_csv<T: DeserializeOwned>(
+        &self,
+        query_id: u32,
+    ) -> Result<Vec<T>, DuneRequestError> {
+        let content = self.get_latest_results_csv_bytes(query_id).await?;
+        DuneClient::_parse_csv(&content)
+    }
+
+    /// Lazily streams result rows (by `job_id`), fetching pages of
+    /// [DEFAULT_PAGE_SIZE](DEFAULT_PAGE_SIZE) rows only as the stream is consumed,
+    /// so that at most one page is held in memory at a time.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// use duners::{client::DuneClient, error::DuneRequestError};
+    /// use futures::TryStreamExt;
+    /// use std::collections::HashMap;
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<(), DuneRequestError> {
+    ///     let dune = DuneClient::from_env();
+    ///     let mut rows = Box::pin(dune.stream_results::<HashMap<String, String>>("01K9QTN27XQTXQV59BKBJ4GKFW"));
+    ///     while let Some(row) = rows.try_next().await? {
+    ///         println!("{:?}", row);
+    ///     }
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn stream_results<T: DeserializeOwned>(
+        &self,
+        job_id: &str,
+    ) -> impl Stream<Item = Result<T, DuneRequestError>> + '_ {
+        self.stream_results_paged(job_id, DEFAULT_PAGE_SIZE)
+    }
+
+    /// Same as [stream_results](DuneClient::stream_results) with a custom `page_size`.
+    pub fn stream_results_paged<T: DeserializeOwned>(
+        &self,
+        job_id: &str,
+        page_size: u32,
+    ) -> impl Stream<Item = Result<T, DuneRequestError>> + '_ {
+        let job_id = job_id.to_string();
+        stream::try_unfold(Some(0), move |offset| {
+            let job_id = job_id.clone();
+            async move {
+                let Some(offset) = offset else {
+                    return Ok::<_, DuneRequestError>(None);
+                };
+                let page = self
+                    .get_results_page::<T>(&job_id, offset, page_size)
+                    .await?;
+                let next_offset = page.next_offset.filter(|next| *next > offset);
+                let rows = stream::iter(page.result.rows.into_iter().map(Ok));
+                Ok(Some((rows, next_offset)))
+            }
+        })
+        .try_flatten()
+    }
+
+    /// Streams status updates of execution `job_id` (checking every 5 seconds),
+    /// yielding whenever its state or queue position changes.
+    /// The stream ends after yielding a terminal state.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// use duners::{client::DuneClient, error::DuneRequestError};
+    /// use futures::TryStreamExt;
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<(), DuneRequestError> {
+    ///     let dune = DuneClient::from_env();
+    ///     let mut updates = Box::pin(dune.watch("01K9QTN27XQTXQV59BKBJ4GKFW"));
+    ///     while let Some(status) = updates.try_next().await? {
+    ///         println!("{:?} (queue position {:?})", status.state, status.queue_position);
+    ///     }
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn watch(
+        &self,
+        job_id: &str,
+    ) -> impl Stream<Item = Result<GetStatusResponse, DuneRequestError>> + '_ {
+        self.watch_with(job_id, RefreshOptions::default().poll)
+    }
+
+    /// Same as [watch](DuneClient::watch), checking the status according to `poll`.
+    pub fn watch_with(
+        &self,
+        job_id: &str,
+        poll: PollStrategy,
+    ) -> impl Stream<Item = Result<GetStatusResponse, DuneRequestError>> + '_ {
+        struct Watch {
+            /// State and queue position of the latest status.
+            last: Option<(ExecutionStatus, Option<u32>)>,
+            checks: u32,
+            delay: Duration,
+            started: Instant,
+            executing_since: Option<Instant>,
+        }
+        let job_id = job_id.to_string();
+        let watch = Watch {
+            last: None,
+            checks: 0,
+            delay: Duration::ZERO,
+            started: Instant::now(),
+            executing_since: None,
+        };
+        stream::try_unfold(watch, move |mut watch| {
+            let job_id = job_id.clone();
+            let poll = poll.clone();
+            async move {
+                loop {
+                    if matches!(&watch.last, Some((state, _)) if state.is_terminal()) {
+                        return Ok(None);
+                    }
+                    sleep(watch.delay).await;
+                    let status = self.get_status(&job_id).await?;
+                    if status.state == ExecutionStatus::Executing && watch.executing_since.is_none()
+                    {
+                        watch.executing_since = Some(Instant::now());
+                    }
+                    watch.delay = poll.delay(
+                        watch.checks,
+                        &status,
+                        watch.executing_since.unwrap_or(watch.started).elapsed(),
+                        self.history.expected(status.query_id),
+                    );
+                    watch.checks += 1;
+                    let current = (status.state.clone(), status.queue_position);
+                    if watch.last.as_ref() != Some(&current) {
+                        watch.last = Some(current);
+                        return Ok(Some((status, watch)));
+                    }
+                }
+            }
+        })
+    }
+
     /// Convenience method for users to
     /// 1. execute,
     /// 2. wait for execution to complete,
     /// 3. fetch and return query results.
+    ///
+    /// Failed (resp. cancelled) executions are returned as
+    /// [ExecutionFailed](DuneRequestError::ExecutionFailed) (with Dune's error details)
+    /// resp. [Cancelled](DuneRequestError::Cancelled) errors, expired ones as
+    /// [Expired](DuneRequestError::Expired) and executions in states unknown to this library
+    /// as [UnknownState](DuneRequestError::UnknownState).
+    /// Truncated results of partially completed executions are returned as such
+    /// (cf. [is_partial](GetResultResponse::is_partial)).
+    ///
+    /// Concurrent refreshes of the same query (with the same parameters, performance
+    /// and time limits) by the client and its clones share a single execution
+    /// (and its results or error). Since the refresh which started the execution began waiting
+    /// first, each refresh still returns within its own time limits. Refreshes with an observer never join
+    /// executions started by other refreshes (so that they observe their execution).
     /// # Arguments
     /// * `query_id` - an integer representing query ID
     ///   (found at the end of a Dune Query URL: [https://dune.com/queries/971694](https://dune.com/queries/971694))
     /// * `parameters` - an optional list of query `Parameter`
     ///   (cf. [https://dune.xyz/queries/3238619](https://dune.xyz/queries/3238619))
-    /// * `ping_frequency` - how frequently (in seconds) should the loop check execution status.
-    ///   Default is 5 seconds. Too frequently could result in rate limiting
-    ///   (i.e. Too Many Requests) especially when executing multiple queries in parallel.
+    /// * `performance` - optional engine size to execute on (cf. [Performance](Performance)).
+    /// * `options` - how frequently to check the execution status (default every 5 seconds)
+    ///   and how long to wait at most (cf. [RefreshOptions](RefreshOptions)).
+    ///   Polling too frequently could result in rate limiting
+    ///   (i.e. Too Many Requests) especially when executing multiple queries in parallel,
+    ///   which can be avoided by configuring a [RateLimiter](RateLimiter) on the client.
+    ///   Executions exceeding the time limits are cancelled, returning a
+    ///   [Timeout](DuneRequestError::Timeout) error.
     ///
     /// # Examples
     /// ```
@@ -180,7 +1031,7 @@ impl DuneClient {
     /// #[tokio::main]
     /// async fn main() -> Result<(), DuneRequestError> {
     ///     let dune = DuneClient::from_env();
-    ///     let results = dune.refresh::<ResultStruct>(1215383, None, None).await?;
+    ///     let results = dune.refresh::<ResultStruct>(1215383, None, None, None).await?;
     ///     println!("{:?}", results.get_rows());
     ///     Ok(())
     /// }
@@ -189,59 +1040,474 @@ impl DuneClient {
         &self,
         query_id: u32,
         parameters: Option<Vec<Parameter>>,
-        ping_frequency: Option<u64>,
+        performance: Option<Performance>,
+        options: Option<RefreshOptions>,
     ) -> Result<GetResultResponse<T>, DuneRequestError> {
-        let job_id = self.execute_query(query_id, parameters).await?.execution_id;
-        info!("Refreshing {} Execution ID {}", query_id, job_id);
-        let mut status = self.get_status(&job_id).await?;
-        while !status.state.is_terminal() {
-            info!(
-                "waiting for query execution {job_id} to complete: {:?}",
-                status.state
-            );
-            sleep(Duration::from_secs(ping_frequency.unwrap_or(5))).await;
-            status = self.get_status(&job_id).await?
-        }
-        let full_response = self.get_results::<T>(&job_id).await;
-        if status.state == ExecutionStatus::Failed {
-            warn!(
-                "{:?} Perhaps your query took too long to run!",
-                status.state
-            );
+        let cache_key = ResultCache::query_key(
+            query_id,
+            parameters.as_deref().unwrap_or_default(),
+            performance,
+        );
+        if let Some(cache) = &self.cache {
+            if let Some(value) = cache.load(&cache_key).await {
+                info!("using cached results of {}", query_id);
+                return DuneClient::_from_value(&value);
+            }
         }
-        full_response
+        // Executions are only shared by refreshes waiting equally long.
+        let limits = options
+            .as_ref()
+            .map(|options| (options.max_wall_time, options.max_queue_wait));
+        let flight_key = format!("{cache_key}-{:?}", limits.unwrap_or_default());
+        let join = options
+            .as_ref()
+            .is_none_or(|options| options.observer.is_none());
+        let refresh = self._refresh_json(query_id, parameters, performance, options, &cache_key);
+        let value = self.in_flight.run(&flight_key, join, refresh).await?;
+        self._typed_results(&value)
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use crate::parse_utils::{date_parse, datetime_from_str, f64_from_str};
-    use crate::response::ExecutionStatus;
-    use chrono::{DateTime, Utc};
-    use serde::Deserialize;
+    /// Raw JSON version of [refresh](DuneClient::refresh) (without the cache lookup),
+    /// caching the results under `cache_key`.
+    async fn _refresh_json(
+        &self,
+        query_id: u32,
+        parameters: Option<Vec<Parameter>>,
+        performance: Option<Performance>,
+        options: Option<RefreshOptions>,
+        cache_key: &str,
+    ) -> Result<Value, DuneRequestError> {
+        let execution = self
+            .execute_query(query_id, parameters, performance)
+            .await?;
+        info!(
+            "Refreshing {} Execution ID {}",
+            query_id, execution.execution_id
+        );
+        let options = options.unwrap_or_default();
+        if let Some(observer) = &options.observer {
+            observer.on_submitted(&execution);
+        }
+        let result = self
+            ._await_results_json(&execution.execution_id, &options)
+            .await;
+        match (&options.observer, &result, &self.cache) {
+            (Some(observer), Err(err), _) => observer.on_failed(&execution.execution_id, err),
+            (_, Ok(_), Some(cache)) => {
+                let execution_key = ResultCache::execution_key(&execution.execution_id);
+                cache.copy(&execution_key, cache_key).await;
+            }
+            _ => {}
+        }
+        result
+    }
 
-    const QUERY_ID: u32 = 971694;
-    const JOB_ID: &str = "01K9QTN27XQTXQV59BKBJ4GKFW";
+    /// Same as [refresh](DuneClient::refresh) for raw SQL (cf. [execute_sql](DuneClient::execute_sql)).
+    pub async fn refresh_sql<T: DeserializeOwned>(
+        &self,
+        sql: &str,
+        performance: Option<Performance>,
+        options: Option<RefreshOptions>,
+    ) -> Result<GetResultResponse<T>, DuneRequestError> {
+        let execution = self.execute_sql(sql, performance).await?;
+        info!("Executing SQL with Execution ID {}", execution.execution_id);
+        if let Some(observer) = options.as_ref().and_then(|o| o.observer.as_ref()) {
+            observer.on_submitted(&execution);
+        }
+        self.await_execution(&execution.execution_id, options).await
+    }
+
+    /// Waits for the existing execution `job_id` to reach a terminal state and fetches its results
+    /// (i.e. the second half of [refresh](DuneClient::refresh)),
+    /// cancelling the execution when it exceeds the time limits of `options`
+    /// (measured from when this method is called).
+    ///
+    /// This allows to resume waiting for executions started by an earlier process,
+    /// given their persisted `execution_id`.
+    pub async fn await_execution<T: DeserializeOwned>(
+        &self,
+        job_id: &str,
+        options: Option<RefreshOptions>,
+    ) -> Result<GetResultResponse<T>, DuneRequestError> {
+        let options = options.unwrap_or_default();
+        let result = self._await_results(job_id, &options).await;
+        if let (Some(observer), Err(err)) = (&options.observer, &result) {
+            observer.on_failed(job_id, err);
+        }
+        result
+    }
+
+    /// Executes a query (cf. [execute_query](DuneClient::execute_query)),
+    /// returning a handle to the execution for checking its status, awaiting or cancelling it.
+    pub async fn submit(
+        &self,
+        query_id: u32,
+        parameters: Option<Vec<Parameter>>,
+        performance: Option<Performance>,
+    ) -> Result<ExecutionHandle, DuneRequestError> {
+        let execution = self
+            .execute_query(query_id, parameters, performance)
+            .await?;
+        Ok(ExecutionHandle::new(self.clone(), &execution.execution_id))
+    }
+
+    /// Same as [submit](DuneClient::submit) for raw SQL.
+    pub async fn submit_sql(
+        &self,
+        sql: &str,
+        performance: Option<Performance>,
+    ) -> Result<ExecutionHandle, DuneRequestError> {
+        let execution = self.execute_sql(sql, performance).await?;
+        Ok(ExecutionHandle::new(self.clone(), &execution.execution_id))
+    }
+
+    /// Implementation of [await_execution](DuneClient::await_execution)
+    /// (reporting status updates to the observer of `options`).
+    async fn _await_results<T: DeserializeOwned>(
+        &self,
+        job_id: &str,
+        options: &RefreshOptions,
+    ) -> Result<GetResultResponse<T>, DuneRequestError> {
+        let value = self._await_results_json(job_id, options).await?;
+        self._typed_results(&value)
+    }
+
+    /// Waits for execution `job_id` as configured by `options`,
+    /// returning its raw JSON results (including the credits reported along with its status).
+    async fn _await_results_json(
+        &self,
+        job_id: &str,
+        options: &RefreshOptions,
+    ) -> Result<Value, DuneRequestError> {
+        let started = Instant::now();
+        let mut executing_since = None;
+        let mut checks = 0;
+        let mut status = self.get_status(job_id).await?;
+        if let Some(observer) = &options.observer {
+            observer.on_status(&status);
+        }
+        while !status.state.is_terminal() {
+            if status.state == ExecutionStatus::Executing && executing_since.is_none() {
+                executing_since = Some(Instant::now());
+            }
+            let queued = status.state == ExecutionStatus::Pending;
+            let remaining = options.remaining(started.elapsed(), queued);
+            if remaining == Some(Duration::ZERO) {
+                let elapsed = started.elapsed();
+                warn!(
+                    "cancelling query execution {job_id} after {:?}: {:?}",
+                    elapsed, status.state
+                );
+                if let Err(err) = self.cancel_execution(job_id).await {
+                    warn!("failed to cancel query execution {job_id}: {err}");
+                }
+                return Err(DuneRequestError::Timeout {
+                    execution_id: job_id.to_string(),
+                    state: status.state,
+                    elapsed,
+                });
+            }
+            info!(
+                "waiting for query execution {job_id} to complete: {:?}",
+                status.state
+            );
+            let delay = options.poll.delay(
+                checks,
+                &status,
+                executing_since.unwrap_or(started).elapsed(),
+                self.history.expected(status.query_id),
+            );
+            sleep(remaining.map_or(delay, |remaining| remaining.min(delay))).await;
+            checks += 1;
+            status = self.get_status(job_id).await?;
+            if let Some(observer) = &options.observer {
+                observer.on_status(&status);
+            }
+        }
+        match status.state {
+            ExecutionStatus::Failed => {
+                warn!("query execution {job_id} failed: {:?}", status.error);
+                return Err(DuneRequestError::ExecutionFailed {
+                    execution_id: job_id.to_string(),
+                    error: status.error,
+                });
+            }
+            ExecutionStatus::Cancelled => {
+                return Err(DuneRequestError::Cancelled {
+                    execution_id: job_id.to_string(),
+                })
+            }
+            ExecutionStatus::Expired => {
+                return Err(DuneRequestError::Expired {
+                    execution_id: job_id.to_string(),
+                })
+            }
+            ExecutionStatus::Unknown(state) => {
+                warn!("query execution {job_id} reached unknown state {state}");
+                return Err(DuneRequestError::UnknownState {
+                    execution_id: job_id.to_string(),
+                    state,
+                });
+            }
+            _ => {}
+        }
+        if let Some(observer) = &options.observer {
+            observer.on_completed(&status);
+        }
+        let mut value = self._get_results_json(job_id).await?;
+        if let (Some(credits), Some(fields)) =
+            (status.execution_cost_credits, value.as_object_mut())
+        {
+            let reported = fields.get("execution_cost_credits");
+            if reported.is_none_or(Value::is_null) {
+                fields.insert("execution_cost_credits".to_string(), credits.into());
+            }
+        }
+        Ok(value)
+    }
+
+    /// Deserializes the raw results of a completed execution,
+    /// remembering its execution time (for adaptive polling).
+    fn _typed_results<T: DeserializeOwned>(
+        &self,
+        value: &Value,
+    ) -> Result<GetResultResponse<T>, DuneRequestError> {
+        let response = DuneClient::_from_value::<GetResultResponse<T>>(value)?;
+        if response.is_partial() {
+            warn!(
+                "query execution {} returned partial (i.e. truncated) results",
+                response.execution_id
+            );
+        }
+        let execution_time = response.result.metadata.execution_time_millis;
+        self.history.record(
+            response.query_id,
+            Duration::from_millis(execution_time.into()),
+        );
+        Ok(response)
+    }
+
+    /// Returns the latest results of a query (cf. [get_latest_results](DuneClient::get_latest_results))
+    /// if they completed less than `max_age` ago, otherwise [refresh](DuneClient::refresh)es the query
+    /// (on the given `performance` tier).
+    /// A new execution is also triggered when Dune has no results for the query yet
+    /// (i.e. responds `404 Not Found`), whereas other errors (e.g. invalid parameters) are returned.
+    pub async fn refresh_if_stale<T: DeserializeOwned>(
+        &self,
+        query_id: u32,
+        parameters: Option<Vec<Parameter>>,
+        performance: Option<Performance>,
+        max_age: Duration,
+    ) -> Result<GetResultResponse<T>, DuneRequestError> {
+        match self
+            .get_latest_results::<T>(query_id, parameters.clone())
+            .await
+        {
+            Ok(latest) if !latest.is_stale(max_age) => {
+                info!(
+                    "using cached results of {} from execution {}",
+                    query_id, latest.execution_id
+                );
+                return Ok(latest);
+            }
+            Ok(latest) => info!(
+                "latest results of {} (execution {}) are stale",
+                query_id, latest.execution_id
+            ),
+            Err(err @ DuneRequestError::NotFound(_)) => {
+                info!("no cached results of {}: {}", query_id, err)
+            }
+            Err(err) => return Err(err),
+        }
+        self.refresh(query_id, parameters, performance, None).await
+    }
+
+    /// [Refresh](DuneClient::refresh)es many parameterized queries, running at most
+    /// `options.concurrency` executions at a time.
+    /// Failing jobs do not affect others: the result of every job is returned
+    /// (in the order of `jobs`) along with aggregate statistics.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// use duners::{batch::BatchOptions, client::DuneClient, parameters::Parameter};
+    /// use std::collections::HashMap;
+    /// use std::time::Duration;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let dune = DuneClient::from_env();
+    ///     let jobs = ["ethereum", "optimism", "arbitrum"]
+    ///         .map(|chain| (3238619, vec![Parameter::text("Chain", chain)]))
+    ///         .to_vec();
+    ///     let options = BatchOptions {
+    ///         concurrency: 2,
+    ///         job_timeout: Some(Duration::from_secs(600)),
+    ///         ..Default::default()
+    ///     };
+    ///     let batch = dune.run_batch::<HashMap<String, String>>(jobs, options).await;
+    ///     println!("{:?}", batch.stats);
+    /// }
+    /// ```
+    pub async fn run_batch<T: DeserializeOwned>(
+        &self,
+        jobs: Vec<(u32, Vec<Parameter>)>,
+        options: BatchOptions,
+    ) -> BatchResult<T> {
+        let started = Instant::now();
+        let refresh = RefreshOptions {
+            max_wall_time: options.job_timeout.or(options.refresh.max_wall_time),
+            ..options.refresh
+        };
+        let results = stream::iter(jobs)
+            .map(|(query_id, parameters)| {
+                let refresh = refresh.clone();
+                async move {
+                    let result = self
+                        .refresh::<T>(
+                            query_id,
+                            Some(parameters),
+                            options.performance,
+                            Some(refresh),
+                        )
+                        .await;
+                    if let Err(err) = &result {
+                        warn!("batch job for query {query_id} failed: {err}");
+                    }
+                    result
+                }
+            })
+            .buffered(options.concurrency.max(1))
+            .collect::<Vec<_>>()
+            .await;
+        let batch = BatchResult::new(results, started.elapsed());
+        info!("batch finished: {:?}", batch.stats);
+        batch
+    }
+
+    /// Executes `backfill.query_id` for every window of `[start, end)` in order,
+    /// lazily yielding the results of each window (cf. [Backfill](Backfill)).
+    /// Windows which time out (or fail due to Dune's time or memory limits) are split in halves
+    /// and executed again (as long as the halves are at least `backfill.min_window` long),
+    /// otherwise their error is yielded and the backfill continues with the next window.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// use duners::backfill::{Backfill, Window};
+    /// use duners::{client::DuneClient, parse_utils::dune_date};
+    /// use futures::StreamExt;
+    /// use std::collections::HashMap;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let dune = DuneClient::from_env();
+    ///     let backfill = Backfill::new(3238619, "StartDate", "EndDate", Window::Week);
+    ///     let (start, end) = (
+    ///         dune_date("2024-01-01 00:00:00").unwrap(),
+    ///         dune_date("2024-04-01 00:00:00").unwrap(),
+    ///     );
+    ///     let mut windows = Box::pin(dune.backfill::<HashMap<String, String>>(backfill, start, end));
+    ///     while let Some(window) = windows.next().await {
+    ///         println!("{} - {}: {:?}", window.start, window.end, window.result.map(|r| r.get_rows()));
+    ///     }
+    /// }
+    /// ```
+    pub fn backfill<T: DeserializeOwned>(
+        &self,
+        backfill: Backfill,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+    ) -> impl Stream<Item = WindowResult<T>> + '_ {
+        let pending = VecDeque::from(windows(start, end, backfill.window));
+        stream::unfold(
+            (backfill, pending),
+            move |(backfill, mut pending)| async move {
+                while let Some((start, end)) = pending.pop_front() {
+                    let result = self
+                        .refresh::<T>(
+                            backfill.query_id,
+                            Some(backfill.parameters(start, end)),
+                            backfill.performance,
+                            backfill.refresh.clone(),
+                        )
+                        .await;
+                    if let Err(err) = &result {
+                        let halves = backfill.split(start, end).filter(|_| is_splittable(err));
+                        if let Some([first, second]) = halves {
+                            warn!("splitting backfill window {start} - {end} after: {err}");
+                            pending.push_front(second);
+                            pending.push_front(first);
+                            continue;
+                        }
+                    }
+                    let window = WindowResult { start, end, result };
+                    return Some((window, (backfill, pending)));
+                }
+                None
+            },
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backfill::Window;
+    use crate::error::{DuneError, ErrorKind};
+    use crate::parse_utils::{date_parse, datetime_from_str, dune_date, f64_from_str};
+    use crate::refresh::RefreshObserver;
+    use crate::response::ExecutionStatus;
+    use crate::table::{Column, ColumnType};
+    use chrono::{DateTime, Utc};
+    use serde::Deserialize;
+    use std::sync::Arc;
+
+    use wiremock::matchers::{body_json, header, method, path, query_param};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    const QUERY_ID: u32 = 971694;
+    const JOB_ID: &str = "01K9QTN27XQTXQV59BKBJ4GKFW";
+
+    #[tokio::test]
+    async fn builder_overrides_transport() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/api/v1/execution/jerb/cancel"))
+            .and(header("x-dune-api-key", "Baloney"))
+            .and(header("x-team", "duners"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({"success": true})))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let dune = DuneClient::builder("Baloney")
+            .base_url(&format!("{}/api/v1/", server.uri()))
+            .http_client(reqwest::Client::new())
+            .default_header("x-team", "duners")
+            .build();
+        let cancellation = dune.cancel_execution("jerb").await.unwrap();
+        assert!(cancellation.success);
+    }
 
     #[tokio::test]
     async fn invalid_api_key() {
         let dune = DuneClient::new("Baloney");
-        let error = dune.execute_query(QUERY_ID, None).await.unwrap_err();
-        assert_eq!(
+        let error = dune.execute_query(QUERY_ID, None, None).await.unwrap_err();
+        assert!(matches!(
             error,
-            DuneRequestError::Dune(String::from("invalid API Key"))
-        )
+            DuneRequestError::Unauthorized(message) if message == "invalid API Key"
+        ))
     }
 
     #[tokio::test]
     async fn invalid_query_id() {
         let dune = DuneClient::from_env();
-        let error = dune.execute_query(u32::MAX, None).await.unwrap_err();
-        assert_eq!(
+        let error = dune.execute_query(u32::MAX, None, None).await.unwrap_err();
+        assert!(matches!(
             error,
-            DuneRequestError::Dune(String::from("An internal error occured"))
-        )
+            DuneRequestError::Server { status, message }
+                if status == reqwest::StatusCode::INTERNAL_SERVER_ERROR
+                    && message == "An internal error occured"
+        ))
     }
 
     #[tokio::test]
@@ -251,18 +1517,97 @@ mod tests {
             .get_results::<DuneError>("wonky job ID")
             .await
             .unwrap_err();
-        assert_eq!(
+        assert!(matches!(
             error,
-            DuneRequestError::Dune(String::from(
-                "The requested execution ID (ID: wonky job ID) is invalid."
-            ))
-        )
+            DuneRequestError::BadRequest(message)
+                if message == "The requested execution ID (ID: wonky job ID) is invalid."
+        ))
+    }
+
+    #[tokio::test]
+    async fn structured_errors() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/execution/throttled/status"))
+            .respond_with(
+                ResponseTemplate::new(429)
+                    .insert_header("retry-after", "3")
+                    .set_body_json(json!({"error": "Too Many Requests"})),
+            )
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/execution/jerb/results"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(completed_results(
+                "jerb",
+                json!([{"price": 1.5}, {"price": "two"}]),
+            )))
+            .mount(&server)
+            .await;
+        let dune = mock_client(&server);
+
+        let error = dune.get_status("throttled").await.unwrap_err();
+        assert!(matches!(
+            error,
+            DuneRequestError::RateLimited { retry_after: Some(delay), ref message }
+                if delay == Duration::from_secs(3) && message == "Too Many Requests"
+        ));
+
+        #[derive(Deserialize, Debug)]
+        #[allow(dead_code)]
+        struct Row {
+            price: f64,
+        }
+        let error = dune.get_results::<Row>("jerb").await.unwrap_err();
+        assert!(matches!(
+            error,
+            DuneRequestError::Deserialize { ref path, .. } if path == "result.rows[1].price"
+        ));
+    }
+
+    #[tokio::test]
+    async fn retries_transient_failures() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/execution/jerb/status"))
+            .respond_with(ResponseTemplate::new(503))
+            .up_to_n_times(2)
+            .expect(2)
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/execution/jerb/status"))
+            .respond_with(
+                ResponseTemplate::new(200).set_body_json(status("jerb", "QUERY_STATE_PENDING")),
+            )
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/query/71/execute"))
+            .respond_with(ResponseTemplate::new(502))
+            .expect(1)
+            .mount(&server)
+            .await;
+        let dune = DuneClient::builder("Baloney")
+            .base_url(&server.uri())
+            .retry_policy(RetryPolicy {
+                base_delay: Duration::from_millis(1),
+                ..Default::default()
+            })
+            .build();
+
+        let status = dune.get_status("jerb").await.unwrap();
+        assert_eq!(status.execution_id, "jerb");
+        // Executions are not idempotent and hence not retried by default.
+        let error = dune.execute_query(71, None, None).await.unwrap_err();
+        assert_eq!(error.status(), Some(reqwest::StatusCode::BAD_GATEWAY));
     }
 
     #[tokio::test]
     async fn execute_query() {
         let dune = DuneClient::from_env();
-        let exec = dune.execute_query(QUERY_ID, None).await.unwrap();
+        let exec = dune.execute_query(QUERY_ID, None, None).await.unwrap();
         // Also testing cancellation!
         let cancellation = dune.cancel_execution(&exec.execution_id).await.unwrap();
         assert!(cancellation.success);
@@ -277,10 +1622,266 @@ mod tests {
             Parameter::text("TextField", "Plain Text"),
             Parameter::list("ListField", "Option 1"),
         ];
-        let exec_result = dune.execute_query(1215383, Some(all_parameter_types)).await;
+        let exec_result = dune
+            .execute_query(
+                1215383,
+                Some(all_parameter_types),
+                Some(Performance::Medium),
+            )
+            .await;
         assert!(exec_result.is_ok())
     }
 
+    #[tokio::test]
+    async fn execute_sql() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/sql/execute"))
+            .and(body_json(
+                json!({"sql": "SELECT 1", "performance": "large"}),
+            ))
+            .respond_with(pending("jerb"))
+            .expect(1)
+            .mount(&server)
+            .await;
+        let dune = mock_client(&server);
+
+        let exec = dune
+            .execute_sql("SELECT 1", Some(Performance::Large))
+            .await
+            .unwrap();
+        assert_eq!(exec.execution_id, "jerb");
+        assert_eq!(exec.state, ExecutionStatus::Pending);
+    }
+
+    #[tokio::test]
+    async fn execute_query_on_large_engine() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/query/71/execute"))
+            .and(body_json(json!({
+                "query_parameters": {"NumberField": "3.14"},
+                "performance": "large"
+            })))
+            .respond_with(pending("jerb"))
+            .expect(1)
+            .mount(&server)
+            .await;
+        let dune = mock_client(&server);
+
+        let exec = dune
+            .execute_query(
+                71,
+                Some(vec![Parameter::number("NumberField", "3.14")]),
+                Some(Performance::Large),
+            )
+            .await
+            .unwrap();
+        assert_eq!(exec.execution_id, "jerb");
+    }
+
+    #[tokio::test]
+    async fn query_management() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/query"))
+            .and(body_json(json!({
+                "name": "Blocks",
+                "query_sql": "SELECT {{Start}}",
+                "parameters": [{"key": "Start", "type": "number", "value": "5"}],
+                "is_private": true
+            })))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({"query_id": 71})))
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("PATCH"))
+            .and(path("/query/71"))
+            .and(body_json(json!({"tags": ["blocks"]})))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({"query_id": 71})))
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/query/71"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "query_id": 71,
+                "name": "Blocks",
+                "description": "",
+                "tags": ["blocks"],
+                "version": 2,
+                "parameters": [{"key": "Start", "type": "number", "value": "5"}],
+                "query_engine": "v2 Dune SQL",
+                "query_sql": "SELECT {{Start}}",
+                "is_private": true,
+                "is_archived": false,
+                "is_unsaved": false,
+                "owner": "bh2smith"
+            })))
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/query/71/archive"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({"query_id": 71})))
+            .expect(1)
+            .mount(&server)
+            .await;
+        let dune = mock_client(&server);
+
+        let created = dune
+            .create_query(&CreateQuery {
+                name: "Blocks".to_string(),
+                query_sql: "SELECT {{Start}}".to_string(),
+                parameters: vec![Parameter::number("Start", "5")],
+                is_private: true,
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+        assert_eq!(created.query_id, 71);
+        let update = UpdateQuery {
+            tags: Some(vec!["blocks".to_string()]),
+            ..Default::default()
+        };
+        dune.update_query(71, &update).await.unwrap();
+        let query = dune.get_query(71).await.unwrap();
+        assert_eq!(query.tags, vec!["blocks"]);
+        assert_eq!(query.parameters, vec![Parameter::number("Start", "5")]);
+        assert!(query.is_private);
+        dune.archive_query(71).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn table_upload() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/table/create"))
+            .and(body_json(json!({
+                "namespace": "duners",
+                "table_name": "prices",
+                "schema": [{"name": "price", "type": "double", "nullable": false}],
+                "is_private": false
+            })))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "namespace": "duners",
+                "table_name": "prices",
+                "full_name": "dune.duners.prices",
+                "example_query": "select * from dune.duners.prices limit 10",
+                "already_existed": false,
+                "message": "Table created successfully"
+            })))
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/table/duners/prices/insert"))
+            .and(header("content-type", "application/x-ndjson"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "rows_written": 2,
+                "bytes_written": 30,
+                "name": "dune.duners.prices"
+            })))
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("DELETE"))
+            .and(path("/table/duners/prices"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "message": "Table duners.prices successfully deleted"
+            })))
+            .expect(1)
+            .mount(&server)
+            .await;
+        let dune = mock_client(&server);
+
+        let created = dune
+            .create_table(&CreateTable {
+                namespace: "duners".to_string(),
+                table_name: "prices".to_string(),
+                schema: vec![Column::new("price", ColumnType::Double, false)],
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+        assert_eq!(created.full_name, "dune.duners.prices");
+        let inserted = dune
+            .insert(
+                "duners",
+                "prices",
+                TableData::NdJson("{\"price\": 1.5}\n{\"price\": 2.5}\n".to_string()),
+            )
+            .await
+            .unwrap();
+        assert_eq!(inserted.rows_written, 2);
+        dune.delete_table("duners", "prices").await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn insert_rows_in_batches() {
+        #[derive(Deserialize, Serialize, Debug, PartialEq)]
+        struct Price {
+            symbol: String,
+            #[serde(deserialize_with = "f64_from_str")]
+            price: f64,
+        }
+        impl TableRow for Price {
+            fn schema() -> Vec<Column> {
+                vec![
+                    Column::new("symbol", ColumnType::Varchar, false),
+                    Column::new("price", ColumnType::Double, false),
+                ]
+            }
+        }
+
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/table/create"))
+            .and(body_json(json!({
+                "namespace": "duners",
+                "table_name": "prices",
+                "schema": [
+                    {"name": "symbol", "type": "varchar", "nullable": false},
+                    {"name": "price", "type": "double", "nullable": false}
+                ],
+                "is_private": false
+            })))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "namespace": "duners",
+                "table_name": "prices",
+                "full_name": "dune.duners.prices",
+                "already_existed": true
+            })))
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/table/duners/prices/insert"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "rows_written": 1,
+                "bytes_written": 32
+            })))
+            .expect(2)
+            .mount(&server)
+            .await;
+        let dune = mock_client(&server);
+
+        // Rows round-trip from the (stringified) format returned by get_results.
+        let rows: Vec<Price> = serde_json::from_value(json!([
+            {"symbol": "WETH", "price": "4148.5"},
+            {"symbol": "GNO", "price": "120.25"}
+        ]))
+        .unwrap();
+        let outcomes = dune
+            .insert_rows_batched("duners", "prices", &rows, 40)
+            .await
+            .unwrap();
+        assert_eq!(
+            outcomes.iter().map(|o| o.rows).collect::<Vec<_>>(),
+            vec![1, 1]
+        );
+        assert!(outcomes.iter().all(|o| o.result.is_ok()));
+    }
+
     #[tokio::test]
     async fn get_status() {
         let dune = DuneClient::from_env();
@@ -308,6 +1909,708 @@ mod tests {
         assert!(rows[0].max_price > 4148.0)
     }
 
+    /// Client sending all requests to `server`.
+    fn mock_client(server: &MockServer) -> DuneClient {
+        DuneClient::builder("Baloney")
+            .base_url(&server.uri())
+            .build()
+    }
+
+    /// Response to executing a query, starting execution `execution_id`.
+    fn pending(execution_id: &str) -> ResponseTemplate {
+        ResponseTemplate::new(200).set_body_json(json!({
+            "execution_id": execution_id,
+            "state": "QUERY_STATE_PENDING"
+        }))
+    }
+
+    /// Status of execution `execution_id` (of query 71) in `state`.
+    fn status(execution_id: &str, state: &str) -> Value {
+        json!({
+            "execution_id": execution_id,
+            "query_id": 71,
+            "state": state,
+            "submitted_at": "2022-01-01T01:02:03.123Z"
+        })
+    }
+
+    /// Results of the completed execution `execution_id` (of query 71), consisting of `rows`.
+    fn completed_results(execution_id: &str, rows: Value) -> Value {
+        let columns = rows
+            .get(0)
+            .and_then(Value::as_object)
+            .map(|row| row.keys().cloned().collect::<Vec<_>>())
+            .unwrap_or_default();
+        let count = rows.as_array().map_or(0, Vec::len);
+        json!({
+            "execution_id": execution_id,
+            "query_id": 71,
+            "state": "QUERY_STATE_COMPLETED",
+            "submitted_at": "2022-01-01T01:02:03.123Z",
+            "result": {
+                "rows": rows,
+                "metadata": {
+                    "column_names": columns,
+                    "result_set_bytes": 10,
+                    "total_row_count": count,
+                    "datapoint_count": count,
+                    "execution_time_millis": 5
+                }
+            }
+        })
+    }
+
+    /// Serves the rows `[1, 2, 3]` of execution "jerb" in pages of 2.
+    async fn paginated_results_server() -> MockServer {
+        let server = MockServer::start().await;
+        let page = |rows: Vec<u32>, next_offset: Option<u64>| {
+            json!({
+                "execution_id": "jerb",
+                "query_id": 71,
+                "state": "QUERY_STATE_COMPLETED",
+                "submitted_at": "2022-01-01T01:02:03.123Z",
+                "result": {
+                    "rows": rows,
+                    "metadata": {
+                        "column_names": ["n"],
+                        "row_count": rows.len(),
+                        "result_set_bytes": 10,
+                        "total_row_count": 3,
+                        "datapoint_count": 3,
+                        "execution_time_millis": 1
+                    }
+                },
+                "next_offset": next_offset,
+                "next_uri": next_offset.map(|o| format!("/execution/jerb/results?offset={o}"))
+            })
+        };
+        for (offset, body) in [(0, page(vec![1, 2], Some(2))), (2, page(vec![3], None))] {
+            Mock::given(method("GET"))
+                .and(path("/execution/jerb/results"))
+                .and(query_param("offset", offset.to_string()))
+                .and(query_param("limit", "2"))
+                .respond_with(ResponseTemplate::new(200).set_body_json(body))
+                .expect(1)
+                .mount(&server)
+                .await;
+        }
+        server
+    }
+
+    #[tokio::test]
+    async fn get_all_results_walks_pages() {
+        let server = paginated_results_server().await;
+        let dune = mock_client(&server);
+
+        let results = dune.get_all_results::<u32>("jerb", 2).await.unwrap();
+        assert_eq!(results.next_offset, None);
+        assert_eq!(results.result.metadata.row_count, Some(3));
+        assert_eq!(results.get_rows(), vec![1, 2, 3]);
+    }
+
+    #[tokio::test]
+    async fn stream_results_pages_lazily() {
+        let server = paginated_results_server().await;
+        let dune = mock_client(&server);
+
+        let rows = dune
+            .stream_results_paged::<u32>("jerb", 2)
+            .try_collect::<Vec<_>>()
+            .await
+            .unwrap();
+        assert_eq!(rows, vec![1, 2, 3]);
+    }
+
+    #[tokio::test]
+    async fn get_results_csv() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/execution/jerb/results/csv"))
+            .respond_with(ResponseTemplate::new(200).set_body_string(
+                "text_field,number_field,date_field\n\
+                 Plain Text,2.5,2022-05-04 00:00:00.000\n",
+            ))
+            .mount(&server)
+            .await;
+        let dune = mock_client(&server);
+
+        #[derive(Deserialize, Debug, PartialEq)]
+        struct CsvRow {
+            text_field: String,
+            #[serde(deserialize_with = "f64_from_str")]
+            number_field: f64,
+            #[serde(deserialize_with = "datetime_from_str")]
+            date_field: DateTime<Utc>,
+        }
+        let rows = dune.get_results_csv::<CsvRow>("jerb").await.unwrap();
+        assert_eq!(
+            rows,
+            vec![CsvRow {
+                text_field: "Plain Text".to_string(),
+                number_field: 2.5,
+                date_field: date_parse("2022-05-04T00:00:00.0Z").unwrap(),
+            }]
+        );
+    }
+
+    #[tokio::test]
+    async fn get_latest_results() {
+        let dune = DuneClient::from_env();
+        let results = dune
+            .get_latest_results::<HashMap<String, serde_json::Value>>(
+                3238619,
+                Some(vec![Parameter::number("NumberField", "3.141592653589793")]),
+            )
+            .await
+            .unwrap();
+        assert_eq!(results.query_id, 3238619);
+        assert_eq!(results.state, ExecutionStatus::Complete);
+    }
+
+    #[tokio::test]
+    async fn refresh_times_out() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/query/71/execute"))
+            .respond_with(pending("stuck"))
+            .mount(&server)
+            .await;
+        let mut stuck = status("stuck", "QUERY_STATE_PENDING");
+        stuck["queue_position"] = json!(12);
+        Mock::given(method("GET"))
+            .and(path("/execution/stuck/status"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(stuck))
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/execution/stuck/cancel"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({"success": true})))
+            .expect(1)
+            .mount(&server)
+            .await;
+        let dune = mock_client(&server);
+
+        let options = RefreshOptions {
+            max_wall_time: Some(Duration::from_secs(60)),
+            max_queue_wait: Some(Duration::from_millis(50)),
+            poll: PollStrategy::Fixed(Duration::from_millis(10)),
+            ..Default::default()
+        };
+        let error = dune
+            .refresh::<HashMap<String, String>>(71, None, None, Some(options))
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            error,
+            DuneRequestError::Timeout { ref execution_id, state: ExecutionStatus::Pending, elapsed }
+                if execution_id == "stuck" && elapsed >= Duration::from_millis(50)
+        ));
+    }
+
+    #[tokio::test]
+    async fn refresh_failed_execution() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/sql/execute"))
+            .respond_with(pending("broken"))
+            .mount(&server)
+            .await;
+        let mut broken = status("broken", "QUERY_STATE_FAILED");
+        broken["error"] = json!({
+            "type": "FAILED_TYPE_EXECUTION_FAILED",
+            "message": "line 1:8: mismatched input",
+            "metadata": {"line": 1, "column": 8}
+        });
+        Mock::given(method("GET"))
+            .and(path("/execution/broken/status"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(broken))
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/execution/broken/results"))
+            .respond_with(ResponseTemplate::new(400))
+            .expect(0)
+            .mount(&server)
+            .await;
+        let dune = mock_client(&server);
+
+        let error = dune
+            .refresh_sql::<HashMap<String, String>>("SELECT SELECT", None, None)
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            error,
+            DuneRequestError::ExecutionFailed { ref execution_id, error: Some(ref error) }
+                if execution_id == "broken" && error.message == "line 1:8: mismatched input"
+        ));
+    }
+
+    #[tokio::test]
+    async fn await_unsuccessful_states() {
+        let server = MockServer::start().await;
+        for (execution_id, state) in [("old", "QUERY_STATE_EXPIRED"), ("new", "QUERY_STATE_NEW")] {
+            Mock::given(method("GET"))
+                .and(path(format!("/execution/{execution_id}/status")))
+                .respond_with(ResponseTemplate::new(200).set_body_json(status(execution_id, state)))
+                .expect(1)
+                .mount(&server)
+                .await;
+        }
+        let dune = mock_client(&server);
+
+        let expired = dune.await_execution::<Value>("old", None).await;
+        assert!(matches!(
+            expired,
+            Err(DuneRequestError::Expired { ref execution_id }) if execution_id == "old"
+        ));
+        // Unknown states end the wait rather than polling forever.
+        let unknown = dune.await_execution::<Value>("new", None).await;
+        assert!(matches!(
+            unknown,
+            Err(DuneRequestError::UnknownState { ref state, .. }) if state == "QUERY_STATE_NEW"
+        ));
+    }
+
+    #[tokio::test]
+    async fn watch_yields_changes() {
+        let server = MockServer::start().await;
+        let queued = |state: &str, queue_position: Option<u32>| {
+            let mut body = status("jerb", state);
+            body["queue_position"] = json!(queue_position);
+            ResponseTemplate::new(200).set_body_json(body)
+        };
+        for (state, queue_position, times) in [
+            ("QUERY_STATE_PENDING", Some(2), 2),
+            ("QUERY_STATE_PENDING", Some(1), 1),
+            ("QUERY_STATE_EXECUTING", None, 3),
+            ("QUERY_STATE_COMPLETED", None, 1),
+        ] {
+            Mock::given(method("GET"))
+                .and(path("/execution/jerb/status"))
+                .respond_with(queued(state, queue_position))
+                .up_to_n_times(times)
+                .expect(times)
+                .mount(&server)
+                .await;
+        }
+        let dune = mock_client(&server);
+
+        let updates = dune
+            .watch_with("jerb", PollStrategy::Fixed(Duration::from_millis(1)))
+            .map_ok(|status| (status.state, status.queue_position))
+            .try_collect::<Vec<_>>()
+            .await
+            .unwrap();
+        assert_eq!(
+            updates,
+            vec![
+                (ExecutionStatus::Pending, Some(2)),
+                (ExecutionStatus::Pending, Some(1)),
+                (ExecutionStatus::Executing, None),
+                (ExecutionStatus::Complete, None),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn refresh_notifies_observer() {
+        #[derive(Default)]
+        struct Recorder(std::sync::Mutex<Vec<String>>);
+
+        impl RefreshObserver for Recorder {
+            fn on_submitted(&self, execution: &ExecutionResponse) {
+                let event = format!("submitted {}", execution.execution_id);
+                self.0.lock().unwrap().push(event);
+            }
+            fn on_status(&self, status: &GetStatusResponse) {
+                let event = format!("status {:?}", status.state);
+                self.0.lock().unwrap().push(event);
+            }
+            fn on_completed(&self, status: &GetStatusResponse) {
+                let event = format!("completed {}", status.execution_id);
+                self.0.lock().unwrap().push(event);
+            }
+            fn on_failed(&self, execution_id: &str, error: &DuneRequestError) {
+                let event = format!("failed {execution_id}: {error}");
+                self.0.lock().unwrap().push(event);
+            }
+        }
+
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/query/71/execute"))
+            .respond_with(pending("jerb"))
+            .mount(&server)
+            .await;
+        for (state, times) in [("QUERY_STATE_EXECUTING", 1), ("QUERY_STATE_COMPLETED", 1)] {
+            Mock::given(method("GET"))
+                .and(path("/execution/jerb/status"))
+                .respond_with(ResponseTemplate::new(200).set_body_json(status("jerb", state)))
+                .up_to_n_times(times)
+                .mount(&server)
+                .await;
+        }
+        Mock::given(method("GET"))
+            .and(path("/execution/jerb/results"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_json(completed_results("jerb", json!([{"number": 1}]))),
+            )
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/execution/gone/status"))
+            .respond_with(
+                ResponseTemplate::new(404).set_body_json(json!({"error": "Execution not found"})),
+            )
+            .mount(&server)
+            .await;
+        let dune = mock_client(&server);
+
+        let recorder = Arc::new(Recorder::default());
+        let options = RefreshOptions {
+            poll: PollStrategy::Fixed(Duration::from_millis(1)),
+            observer: Some(recorder.clone()),
+            ..Default::default()
+        };
+        let results = dune
+            .refresh::<HashMap<String, u32>>(71, None, None, Some(options.clone()))
+            .await
+            .unwrap();
+        assert_eq!(results.get_rows()[0]["number"], 1);
+        // Resuming an unknown execution
+        let error = dune
+            .await_execution::<HashMap<String, u32>>("gone", Some(options))
+            .await
+            .unwrap_err();
+        assert!(matches!(error, DuneRequestError::NotFound(_)));
+        assert_eq!(
+            *recorder.0.lock().unwrap(),
+            vec![
+                "submitted jerb",
+                "status Executing",
+                "status Complete",
+                "completed jerb",
+                "failed gone: not found: Execution not found",
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn refresh_if_stale_without_results() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/query/71/results"))
+            .respond_with(
+                ResponseTemplate::new(404).set_body_json(json!({"error": "No results found"})),
+            )
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/query/72/results"))
+            .respond_with(
+                ResponseTemplate::new(400).set_body_json(json!({"error": "invalid parameter"})),
+            )
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/query/71/execute"))
+            .respond_with(pending("jerb"))
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/execution/jerb/status"))
+            .respond_with(
+                ResponseTemplate::new(200).set_body_json(status("jerb", "QUERY_STATE_COMPLETED")),
+            )
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/execution/jerb/results"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_json(completed_results("jerb", json!([{"number": 1}]))),
+            )
+            .mount(&server)
+            .await;
+        let dune = mock_client(&server);
+        let hour = Duration::from_secs(3600);
+
+        let results = dune
+            .refresh_if_stale::<Value>(71, None, None, hour)
+            .await
+            .unwrap();
+        assert_eq!(results.execution_id, "jerb");
+        // Invalid requests would fail the same way when executing.
+        let error = dune
+            .refresh_if_stale::<Value>(72, None, None, hour)
+            .await
+            .unwrap_err();
+        assert!(matches!(error, DuneRequestError::BadRequest(ref m) if m == "invalid parameter"));
+    }
+
+    #[tokio::test]
+    async fn batch_isolates_failures() {
+        let server = MockServer::start().await;
+        for (chain, credits) in [("ethereum", 1.5), ("optimism", 0.25)] {
+            Mock::given(method("POST"))
+                .and(path("/query/71/execute"))
+                .and(body_json(json!({"query_parameters": {"Chain": chain}})))
+                .respond_with(pending(chain))
+                .mount(&server)
+                .await;
+            let mut completed = status(chain, "QUERY_STATE_COMPLETED");
+            completed["execution_cost_credits"] = json!(credits);
+            Mock::given(method("GET"))
+                .and(path(format!("/execution/{chain}/status")))
+                .respond_with(ResponseTemplate::new(200).set_body_json(completed))
+                .mount(&server)
+                .await;
+            Mock::given(method("GET"))
+                .and(path(format!("/execution/{chain}/results")))
+                .respond_with(
+                    ResponseTemplate::new(200)
+                        .set_body_json(completed_results(chain, json!([{"chain": chain}]))),
+                )
+                .mount(&server)
+                .await;
+        }
+        Mock::given(method("POST"))
+            .and(path("/query/71/execute"))
+            .and(body_json(json!({"query_parameters": {"Chain": "bogus"}})))
+            .respond_with(
+                ResponseTemplate::new(400).set_body_json(json!({"error": "invalid parameter"})),
+            )
+            .mount(&server)
+            .await;
+        let dune = mock_client(&server);
+
+        let jobs = ["ethereum", "bogus", "optimism"]
+            .map(|chain| (71, vec![Parameter::text("Chain", chain)]))
+            .to_vec();
+        let options = BatchOptions {
+            concurrency: 2,
+            ..Default::default()
+        };
+        let batch = dune
+            .run_batch::<HashMap<String, String>>(jobs, options)
+            .await;
+        let chains = batch
+            .results
+            .into_iter()
+            .map(|result| result.map(|response| response.get_rows()[0]["chain"].clone()))
+            .collect::<Vec<_>>();
+        assert!(matches!(&chains[0], Ok(chain) if chain == "ethereum"));
+        assert!(matches!(&chains[1], Err(DuneRequestError::BadRequest(_))));
+        assert!(matches!(&chains[2], Ok(chain) if chain == "optimism"));
+        assert_eq!(batch.stats.succeeded, 2);
+        assert_eq!(batch.stats.failed, 1);
+        assert_eq!(batch.stats.credits, 1.75);
+    }
+
+    #[tokio::test]
+    async fn backfill_splits_failed_windows() {
+        let server = MockServer::start().await;
+        let execute = |start: &str, end: &str, execution_id: &str| {
+            Mock::given(method("POST"))
+                .and(path("/query/71/execute"))
+                .and(body_json(json!({
+                    "query_parameters": {"Start": start, "End": end}
+                })))
+                .respond_with(pending(execution_id))
+        };
+        execute("2024-01-01 00:00:00", "2024-01-02 00:00:00", "day1")
+            .mount(&server)
+            .await;
+        execute("2024-01-02 00:00:00", "2024-01-03 00:00:00", "day2")
+            .mount(&server)
+            .await;
+        execute("2024-01-02 00:00:00", "2024-01-02 12:00:00", "morning")
+            .mount(&server)
+            .await;
+        execute("2024-01-02 12:00:00", "2024-01-03 00:00:00", "evening")
+            .mount(&server)
+            .await;
+        let failure =
+            |message: &str| json!({"type": "FAILED_TYPE_EXECUTION_FAILED", "message": message});
+        for (execution_id, state, error) in [
+            // Would fail the same way for any window
+            ("day1", "QUERY_STATE_FAILED", failure("Division by zero")),
+            (
+                "day2",
+                "QUERY_STATE_FAILED",
+                failure("Query exceeded per-node memory limit"),
+            ),
+            ("morning", "QUERY_STATE_COMPLETED", Value::Null),
+            ("evening", "QUERY_STATE_COMPLETED", Value::Null),
+        ] {
+            let mut body = status(execution_id, state);
+            body["error"] = error;
+            Mock::given(method("GET"))
+                .and(path(format!("/execution/{execution_id}/status")))
+                .respond_with(ResponseTemplate::new(200).set_body_json(body))
+                .mount(&server)
+                .await;
+            Mock::given(method("GET"))
+                .and(path(format!("/execution/{execution_id}/results")))
+                .respond_with(
+                    ResponseTemplate::new(200)
+                        .set_body_json(completed_results(execution_id, json!([]))),
+                )
+                .mount(&server)
+                .await;
+        }
+        let dune = mock_client(&server);
+
+        let mut backfill = Backfill::new(71, "Start", "End", Window::Day);
+        backfill.min_window = Duration::from_secs(6 * 3600);
+        let windows = dune
+            .backfill::<HashMap<String, String>>(
+                backfill,
+                dune_date("2024-01-01 00:00:00").unwrap(),
+                dune_date("2024-01-03 00:00:00").unwrap(),
+            )
+            .map(|window| window.result.map(|r| r.execution_id).map_err(|e| e.kind()))
+            .collect::<Vec<_>>()
+            .await;
+        assert_eq!(
+            windows,
+            vec![
+                Err(ErrorKind::ExecutionFailed),
+                Ok("morning".to_string()),
+                Ok("evening".to_string())
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn cached_results() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/query/71/execute"))
+            .respond_with(pending("jerb"))
+            .expect(2)
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/execution/jerb/status"))
+            .respond_with(
+                ResponseTemplate::new(200).set_body_json(status("jerb", "QUERY_STATE_COMPLETED")),
+            )
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/execution/jerb/results"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_json(completed_results("jerb", json!([{"number": 1}]))),
+            )
+            .expect(2)
+            .mount(&server)
+            .await;
+        let dir = std::env::temp_dir().join(format!("duners-cached-{}", std::process::id()));
+        let dune = DuneClient::builder("Baloney")
+            .base_url(&server.uri())
+            .cache(ResultCache::new(&dir, Duration::from_secs(60)))
+            .build();
+        let options = Some(RefreshOptions::polling_every(Duration::from_millis(1)));
+
+        let params = Some(vec![Parameter::number("n", "1")]);
+        for _ in 0..2 {
+            let results = dune
+                .refresh::<HashMap<String, u32>>(71, params.clone(), None, options.clone())
+                .await
+                .unwrap();
+            assert_eq!(
+                results.get_rows(),
+                vec![HashMap::from([("number".into(), 1)])]
+            );
+        }
+        // Cached per execution as well
+        dune.get_results::<Value>("jerb").await.unwrap();
+        // A forced refresh executes (and fetches) again.
+        dune.with_cache_mode(CacheMode::Refresh)
+            .refresh::<Value>(71, params, None, options)
+            .await
+            .unwrap();
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn concurrent_refreshes_share_execution() {
+        let server = MockServer::start().await;
+        for (query_id, execution_id, executions) in [(71, "jerb", 1), (72, "oops", 3)] {
+            Mock::given(method("POST"))
+                .and(path(format!("/query/{query_id}/execute")))
+                .respond_with(pending(execution_id))
+                .expect(executions)
+                .mount(&server)
+                .await;
+        }
+        for (execution_id, state) in [
+            ("jerb", "QUERY_STATE_COMPLETED"),
+            ("oops", "QUERY_STATE_FAILED"),
+        ] {
+            Mock::given(method("GET"))
+                .and(path(format!("/execution/{execution_id}/status")))
+                .respond_with(
+                    ResponseTemplate::new(200)
+                        .set_body_json(status(execution_id, state))
+                        .set_delay(Duration::from_millis(50)),
+                )
+                .mount(&server)
+                .await;
+        }
+        Mock::given(method("GET"))
+            .and(path("/execution/jerb/results"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_json(completed_results("jerb", json!([{"number": 1}]))),
+            )
+            .expect(1)
+            .mount(&server)
+            .await;
+        let dune = mock_client(&server);
+
+        // Clones share executions, whose results callers may deserialize differently.
+        let clone = dune.clone();
+        let (numbers, values) = futures::join!(
+            dune.refresh::<HashMap<String, u32>>(71, None, None, None),
+            clone.refresh::<Value>(71, None, None, None),
+        );
+        assert_eq!(
+            numbers.unwrap().get_rows(),
+            vec![HashMap::from([("number".into(), 1)])]
+        );
+        assert_eq!(values.unwrap().get_rows(), vec![json!({"number": 1})]);
+
+        // Refreshes with other time limits or an observer execute on their own.
+        struct Silent;
+        impl RefreshObserver for Silent {}
+        let limited = RefreshOptions {
+            max_wall_time: Some(Duration::from_secs(60)),
+            ..Default::default()
+        };
+        let observed = RefreshOptions {
+            observer: Some(Arc::new(Silent)),
+            ..Default::default()
+        };
+        let (first, second, third, fourth) = futures::join!(
+            dune.refresh::<Value>(72, None, None, None),
+            dune.refresh::<Value>(72, None, None, None),
+            dune.refresh::<Value>(72, None, None, Some(limited)),
+            dune.refresh::<Value>(72, None, None, Some(observed)),
+        );
+        // Every caller receives the same error variant.
+        for err in [first, second, third, fourth].map(Result::unwrap_err) {
+            assert!(matches!(
+                err,
+                DuneRequestError::ExecutionFailed { ref execution_id, .. } if execution_id == "oops"
+            ));
+        }
+    }
+
     #[tokio::test]
     async fn refresh() {
         let dune = DuneClient::from_env();
@@ -326,6 +2629,7 @@ mod tests {
                 3238619,
                 Some(vec![Parameter::number("NumberField", "3.141592653589793")]),
                 None,
+                None,
             )
             .await
             .unwrap();
@@ -345,7 +2649,7 @@ mod tests {
     async fn long_running_query() {
         let dune = DuneClient::from_env();
         let results = dune
-            .refresh::<HashMap<String, f64>>(1229120, None, None)
+            .refresh::<HashMap<String, f64>>(1229120, None, Some(Performance::Large), None)
             .await
             .unwrap();
         println!("Job ID {:?}", results.execution_id);
@@ -1,4 +1,14 @@
+use crate::response::{ExecutionError, ExecutionStatus};
+use chrono::{DateTime, Utc};
+use reqwest::StatusCode;
 use serde::Deserialize;
+use std::error::Error;
+use std::fmt;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Maximum number of characters of a response body kept in errors.
+const SNIPPET_LENGTH: usize = 256;
 
 /// Encapsulates any "unexpected" data
 /// returned from Dune upon bad request.
@@ -7,26 +17,272 @@ pub struct DuneError {
     pub error: String,
 }
 
-#[derive(Debug, PartialEq)]
+/// Everything that can go wrong when talking to Dune,
+/// distinguished by cause so that callers can decide how to react (e.g. whether to retry).
+///
+/// Errors are cheaply cloneable (underlying errors are reference counted),
+/// e.g. for handing the same error to all refreshes sharing an execution.
+#[derive(Clone, Debug)]
 pub enum DuneRequestError {
-    /// Includes known errors:
-    /// "invalid API Key"
-    /// "Query not found"
-    /// "The requested execution ID (ID: wonky job ID) is invalid."
-    Dune(String),
-    /// Errors bubbled up from reqwest::Error
-    Request(String),
+    /// HTTP 400: the request was rejected,
+    /// e.g. "The requested execution ID (ID: wonky job ID) is invalid."
+    BadRequest(String),
+    /// HTTP 401: e.g. "invalid API Key"
+    Unauthorized(String),
+    /// HTTP 402: the account ran out of credits (or the route requires a paid plan).
+    PaymentRequired(String),
+    /// HTTP 403: the API key may not access the requested resource.
+    Forbidden(String),
+    /// HTTP 404: e.g. "Query not found"
+    NotFound(String),
+    /// HTTP 429: Too Many Requests,
+    /// along with the delay requested by Dune's `Retry-After` header (if any).
+    RateLimited {
+        retry_after: Option<Duration>,
+        message: String,
+    },
+    /// HTTP 5xx: Dune (or a gateway in between) failed to handle the request.
+    Server { status: StatusCode, message: String },
+    /// Any other unsuccessful HTTP status.
+    Http { status: StatusCode, message: String },
+    /// A successful response could not be deserialized into the expected type.
+    Deserialize {
+        /// Beginning of the response body.
+        body_snippet: String,
+        /// Location of the offending value (e.g. `result.rows[3].price`).
+        path: String,
+        source: Arc<dyn Error + Send + Sync>,
+    },
+    /// Rows could not be serialized for upload.
+    Serialize(Arc<serde_json::Error>),
+    /// Errors bubbled up from reqwest::Error (connection failures, timeouts, ...)
+    Transport(Arc<reqwest::Error>),
+    /// Number of result rows received differs from what Dune reported
+    /// (e.g. when concatenating paginated results).
+    UnexpectedRowCount { expected: u64, received: u64 },
+    /// The execution did not finish within the limits of its
+    /// [RefreshOptions](crate::refresh::RefreshOptions) and was cancelled.
+    Timeout {
+        execution_id: String,
+        /// Last observed state (i.e. whether the execution was still queued).
+        state: ExecutionStatus,
+        elapsed: Duration,
+    },
+    /// The execution failed (with Dune's explanation, if provided).
+    ExecutionFailed {
+        execution_id: String,
+        error: Option<ExecutionError>,
+    },
+    /// The execution was cancelled before completing.
+    Cancelled { execution_id: String },
+    /// The execution expired (i.e. its results are no longer stored).
+    Expired { execution_id: String },
+    /// The execution reached a state unknown to this library (treated as terminal).
+    UnknownState { execution_id: String, state: String },
+}
+
+/// Payload-free counterpart of [DuneRequestError](DuneRequestError),
+/// e.g. for configuring which errors are retried.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ErrorKind {
+    BadRequest,
+    Unauthorized,
+    PaymentRequired,
+    Forbidden,
+    NotFound,
+    RateLimited,
+    Server,
+    Http,
+    Deserialize,
+    Serialize,
+    Transport,
+    UnexpectedRowCount,
+    Timeout,
+    ExecutionFailed,
+    Cancelled,
+    Expired,
+    UnknownState,
+}
+
+impl DuneRequestError {
+    /// Error corresponding to an unsuccessful HTTP `status` with response `body`
+    /// (usually JSON of the form `{"error": "..."}`).
+    pub fn from_status(status: StatusCode, retry_after: Option<Duration>, body: &str) -> Self {
+        let message = match serde_json::from_str::<DuneError>(body) {
+            Ok(err) => err.error,
+            Err(_) => snippet(body),
+        };
+        match status {
+            StatusCode::BAD_REQUEST => DuneRequestError::BadRequest(message),
+            StatusCode::UNAUTHORIZED => DuneRequestError::Unauthorized(message),
+            StatusCode::PAYMENT_REQUIRED => DuneRequestError::PaymentRequired(message),
+            StatusCode::FORBIDDEN => DuneRequestError::Forbidden(message),
+            StatusCode::NOT_FOUND => DuneRequestError::NotFound(message),
+            StatusCode::TOO_MANY_REQUESTS => DuneRequestError::RateLimited {
+                retry_after,
+                message,
+            },
+            status if status.is_server_error() => DuneRequestError::Server { status, message },
+            status => DuneRequestError::Http { status, message },
+        }
+    }
+
+    /// Error for a `body` which failed to deserialize at `path`.
+    pub fn deserialize(
+        body: &str,
+        path: String,
+        source: impl Into<Box<dyn Error + Send + Sync>>,
+    ) -> Self {
+        DuneRequestError::Deserialize {
+            body_snippet: snippet(body),
+            path,
+            source: Arc::from(source.into()),
+        }
+    }
+
+    /// HTTP status returned by Dune (None for errors not caused by an unsuccessful response).
+    pub fn status(&self) -> Option<StatusCode> {
+        match self {
+            DuneRequestError::BadRequest(_) => Some(StatusCode::BAD_REQUEST),
+            DuneRequestError::Unauthorized(_) => Some(StatusCode::UNAUTHORIZED),
+            DuneRequestError::PaymentRequired(_) => Some(StatusCode::PAYMENT_REQUIRED),
+            DuneRequestError::Forbidden(_) => Some(StatusCode::FORBIDDEN),
+            DuneRequestError::NotFound(_) => Some(StatusCode::NOT_FOUND),
+            DuneRequestError::RateLimited { .. } => Some(StatusCode::TOO_MANY_REQUESTS),
+            DuneRequestError::Server { status, .. } | DuneRequestError::Http { status, .. } => {
+                Some(*status)
+            }
+            DuneRequestError::Transport(err) => err.status(),
+            _ => None,
+        }
+    }
+
+    /// Category of this error.
+    pub fn kind(&self) -> ErrorKind {
+        match self {
+            DuneRequestError::BadRequest(_) => ErrorKind::BadRequest,
+            DuneRequestError::Unauthorized(_) => ErrorKind::Unauthorized,
+            DuneRequestError::PaymentRequired(_) => ErrorKind::PaymentRequired,
+            DuneRequestError::Forbidden(_) => ErrorKind::Forbidden,
+            DuneRequestError::NotFound(_) => ErrorKind::NotFound,
+            DuneRequestError::RateLimited { .. } => ErrorKind::RateLimited,
+            DuneRequestError::Server { .. } => ErrorKind::Server,
+            DuneRequestError::Http { .. } => ErrorKind::Http,
+            DuneRequestError::Deserialize { .. } => ErrorKind::Deserialize,
+            DuneRequestError::Serialize(_) => ErrorKind::Serialize,
+            DuneRequestError::Transport(_) => ErrorKind::Transport,
+            DuneRequestError::UnexpectedRowCount { .. } => ErrorKind::UnexpectedRowCount,
+            DuneRequestError::Timeout { .. } => ErrorKind::Timeout,
+            DuneRequestError::ExecutionFailed { .. } => ErrorKind::ExecutionFailed,
+            DuneRequestError::Cancelled { .. } => ErrorKind::Cancelled,
+            DuneRequestError::Expired { .. } => ErrorKind::Expired,
+            DuneRequestError::UnknownState { .. } => ErrorKind::UnknownState,
+        }
+    }
+}
+
+/// Parses `Retry-After` header values, given either in seconds or as HTTP date.
+pub fn parse_retry_after(value: &str) -> Option<Duration> {
+    let value = value.trim();
+    if let Ok(seconds) = value.parse::<u64>() {
+        return Some(Duration::from_secs(seconds));
+    }
+    let date = DateTime::parse_from_rfc2822(value).ok()?;
+    (date.with_timezone(&Utc) - Utc::now()).to_std().ok()
 }
 
-impl From<DuneError> for DuneRequestError {
-    fn from(value: DuneError) -> Self {
-        DuneRequestError::Dune(value.error)
+fn snippet(body: &str) -> String {
+    body.chars().take(SNIPPET_LENGTH).collect()
+}
+
+impl fmt::Display for DuneRequestError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DuneRequestError::BadRequest(message) => write!(f, "bad request: {message}"),
+            DuneRequestError::Unauthorized(message) => write!(f, "unauthorized: {message}"),
+            DuneRequestError::PaymentRequired(message) => {
+                write!(f, "payment required: {message}")
+            }
+            DuneRequestError::Forbidden(message) => write!(f, "forbidden: {message}"),
+            DuneRequestError::NotFound(message) => write!(f, "not found: {message}"),
+            DuneRequestError::RateLimited {
+                retry_after,
+                message,
+            } => match retry_after {
+                Some(delay) => write!(f, "rate limited (retry after {delay:?}): {message}"),
+                None => write!(f, "rate limited: {message}"),
+            },
+            DuneRequestError::Server { status, message }
+            | DuneRequestError::Http { status, message } => write!(f, "{status}: {message}"),
+            DuneRequestError::Deserialize {
+                body_snippet,
+                path,
+                source,
+            } => write!(
+                f,
+                "failed to deserialize response at `{path}`: {source} (body: {body_snippet})"
+            ),
+            DuneRequestError::Serialize(err) => write!(f, "failed to serialize rows: {err}"),
+            DuneRequestError::Transport(err) => write!(f, "request failed: {err}"),
+            DuneRequestError::UnexpectedRowCount { expected, received } => {
+                write!(f, "expected {expected} result rows but received {received}")
+            }
+            DuneRequestError::Timeout {
+                execution_id,
+                state,
+                elapsed,
+            } => write!(
+                f,
+                "execution {execution_id} timed out after {elapsed:?} in state {state:?}"
+            ),
+            DuneRequestError::ExecutionFailed {
+                execution_id,
+                error,
+            } => {
+                write!(f, "execution {execution_id} failed")?;
+                let Some(error) = error else {
+                    return Ok(());
+                };
+                write!(f, ": {} ({})", error.message, error.error_type)?;
+                match error.metadata.as_ref().and_then(|m| m.line.zip(m.column)) {
+                    Some((line, column)) => write!(f, " at line {line}, column {column}"),
+                    None => Ok(()),
+                }
+            }
+            DuneRequestError::Cancelled { execution_id } => {
+                write!(f, "execution {execution_id} was cancelled")
+            }
+            DuneRequestError::Expired { execution_id } => {
+                write!(f, "execution {execution_id} expired")
+            }
+            DuneRequestError::UnknownState {
+                execution_id,
+                state,
+            } => write!(f, "execution {execution_id} reached unknown state {state}"),
+        }
+    }
+}
+
+impl Error for DuneRequestError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            DuneRequestError::Deserialize { source, .. } => Some(source.as_ref()),
+            DuneRequestError::Serialize(err) => Some(err.as_ref()),
+            DuneRequestError::Transport(err) => Some(err.as_ref()),
+            _ => None,
+        }
+    }
+}
+
+impl From<serde_json::Error> for DuneRequestError {
+    fn from(value: serde_json::Error) -> Self {
+        DuneRequestError::Serialize(Arc::new(value))
     }
 }
 
 impl From<reqwest::Error> for DuneRequestError {
     fn from(value: reqwest::Error) -> Self {
-        DuneRequestError::Request(value.to_string())
+        DuneRequestError::Transport(Arc::new(value))
     }
 }
 
@@ -37,16 +293,63 @@ mod tests {
     #[tokio::test]
     async fn error_parsing() {
         let err = reqwest::get("invalid-url").await.unwrap_err();
-        assert_eq!(
-            DuneRequestError::from(err),
-            DuneRequestError::Request("builder error".to_string())
+        let err = DuneRequestError::from(err);
+        assert!(matches!(err, DuneRequestError::Transport(_)));
+        assert_eq!(err.to_string(), "request failed: builder error");
+        assert!(err.source().is_some());
+    }
+
+    #[test]
+    fn from_status() {
+        let body = r#"{"error": "invalid API Key"}"#;
+        let err = DuneRequestError::from_status(StatusCode::UNAUTHORIZED, None, body);
+        assert!(matches!(&err, DuneRequestError::Unauthorized(m) if m == "invalid API Key"));
+        assert_eq!(err.status(), Some(StatusCode::UNAUTHORIZED));
+        assert_eq!(err.kind(), ErrorKind::Unauthorized);
+
+        let err = DuneRequestError::from_status(
+            StatusCode::TOO_MANY_REQUESTS,
+            Some(Duration::from_secs(7)),
+            r#"{"error": "slow down"}"#,
         );
+        assert!(matches!(
+            err,
+            DuneRequestError::RateLimited { retry_after: Some(d), .. } if d == Duration::from_secs(7)
+        ));
+
+        // Non-JSON bodies (e.g. from gateways) are kept as message.
+        let err = DuneRequestError::from_status(StatusCode::BAD_GATEWAY, None, "<html>");
+        assert!(matches!(
+            &err,
+            DuneRequestError::Server { status, message }
+                if *status == StatusCode::BAD_GATEWAY && message == "<html>"
+        ));
+        assert_eq!(err.to_string(), "502 Bad Gateway: <html>");
+    }
+
+    #[test]
+    fn execution_failed() {
+        let err = DuneRequestError::ExecutionFailed {
+            execution_id: "jerb".to_string(),
+            error: serde_json::from_str(
+                r#"{"type": "FAILED_TYPE_EXECUTION_FAILED", "message": "syntax error", "metadata": {"line": 3, "column": 14}}"#,
+            )
+            .unwrap(),
+        };
         assert_eq!(
-            DuneRequestError::from(DuneError {
-                error: "broken".to_string()
-            }),
-            DuneRequestError::Dune("broken".to_string())
-        )
+            err.to_string(),
+            "execution jerb failed: syntax error (FAILED_TYPE_EXECUTION_FAILED) at line 3, column 14"
+        );
+        assert_eq!(err.kind(), ErrorKind::ExecutionFailed);
+    }
+
+    #[test]
+    fn retry_after() {
+        assert_eq!(parse_retry_after("120"), Some(Duration::from_secs(120)));
+        assert_eq!(parse_retry_after("Wed, 21 Oct 2015 07:28:00 GMT"), None);
+        let in_a_minute = (Utc::now() + chrono::TimeDelta::seconds(61)).to_rfc2822();
+        assert!(parse_retry_after(&in_a_minute).unwrap() > Duration::from_secs(59));
+        assert_eq!(parse_retry_after("soon"), None);
     }
 
     #[test]
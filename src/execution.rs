@@ -0,0 +1,263 @@
+use crate::client::DuneClient;
+use crate::error::{DuneRequestError, ErrorKind};
+use crate::refresh::RefreshOptions;
+use crate::response::{
+    CancellationResponse, ExecutionStatus, GetResultResponse, GetStatusResponse,
+};
+use log::{info, warn};
+use serde::de::DeserializeOwned;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// Handle to a submitted execution (cf. [submit](DuneClient::submit)),
+/// owning its `execution_id`.
+///
+/// With [cancel_on_drop](ExecutionHandle::cancel_on_drop), dropping the handle
+/// (e.g. when aborting the task awaiting it) before the execution reached a terminal state
+/// cancels it, so that abandoned executions stop consuming credits. Executions last seen
+/// in a state unknown to this library are cancelled as well, as they may still be running.
+///
+/// # Examples
+/// ```no_run
+/// use duners::{client::DuneClient, error::DuneRequestError};
+/// use std::collections::HashMap;
+///
+/// #[tokio::main]
+/// async fn main() -> Result<(), DuneRequestError> {
+///     let dune = DuneClient::from_env();
+///     let execution = dune.submit(1215383, None, None).await?.cancel_on_drop(true);
+///     println!("submitted {}", execution.execution_id());
+///     let results = execution.wait::<HashMap<String, String>>(None).await?;
+///     println!("{:?}", results.get_rows());
+///     Ok(())
+/// }
+/// ```
+pub struct ExecutionHandle {
+    client: DuneClient,
+    execution_id: String,
+    cancel_on_drop: bool,
+    /// Whether the execution is known to have reached a terminal state.
+    finished: AtomicBool,
+}
+
+impl ExecutionHandle {
+    /// Handle to the existing execution `execution_id`
+    /// (e.g. to resume an execution started by an earlier process).
+    pub fn new(client: DuneClient, execution_id: &str) -> Self {
+        ExecutionHandle {
+            client,
+            execution_id: execution_id.to_string(),
+            cancel_on_drop: false,
+            finished: AtomicBool::new(false),
+        }
+    }
+
+    /// Cancels the execution when the handle is dropped before the execution finished
+    /// (requires a running tokio runtime at that point).
+    pub fn cancel_on_drop(mut self, enabled: bool) -> Self {
+        self.cancel_on_drop = enabled;
+        self
+    }
+
+    /// ID of the execution (e.g. to persist it for resuming later).
+    pub fn execution_id(&self) -> &str {
+        &self.execution_id
+    }
+
+    /// Releases the execution from the handle (never cancelling it), returning its ID.
+    pub fn detach(mut self) -> String {
+        self.cancel_on_drop = false;
+        std::mem::take(&mut self.execution_id)
+    }
+
+    /// Current status of the execution (cf. [get_status](DuneClient::get_status)).
+    pub async fn status(&self) -> Result<GetStatusResponse, DuneRequestError> {
+        let status = self.client.get_status(&self.execution_id).await?;
+        if is_finished(&status.state) {
+            self.finished.store(true, Ordering::Relaxed);
+        }
+        Ok(status)
+    }
+
+    /// Waits for the execution to finish and fetches its results
+    /// (cf. [await_execution](DuneClient::await_execution)).
+    pub async fn wait<T: DeserializeOwned>(
+        &self,
+        options: Option<RefreshOptions>,
+    ) -> Result<GetResultResponse<T>, DuneRequestError> {
+        let result = self
+            .client
+            .await_execution::<T>(&self.execution_id, options)
+            .await;
+        let finished = match &result {
+            Ok(_) => true,
+            Err(err) => matches!(
+                err.kind(),
+                ErrorKind::Timeout
+                    | ErrorKind::ExecutionFailed
+                    | ErrorKind::Cancelled
+                    | ErrorKind::Expired
+            ),
+        };
+        if finished {
+            self.finished.store(true, Ordering::Relaxed);
+        }
+        result
+    }
+
+    /// Results of the execution, without waiting for it to finish
+    /// (cf. [get_results](DuneClient::get_results)).
+    pub async fn results<T: DeserializeOwned>(
+        &self,
+    ) -> Result<GetResultResponse<T>, DuneRequestError> {
+        let results = self.client.get_results::<T>(&self.execution_id).await?;
+        if is_finished(&results.state) {
+            self.finished.store(true, Ordering::Relaxed);
+        }
+        Ok(results)
+    }
+
+    /// Cancels the execution (cf. [cancel_execution](DuneClient::cancel_execution)).
+    pub async fn cancel(&self) -> Result<CancellationResponse, DuneRequestError> {
+        let cancellation = self.client.cancel_execution(&self.execution_id).await?;
+        self.finished.store(true, Ordering::Relaxed);
+        Ok(cancellation)
+    }
+}
+
+/// Whether `state` is known to be terminal
+/// (unknown states end waits, but might be running nonetheless).
+fn is_finished(state: &ExecutionStatus) -> bool {
+    state.is_terminal() && !matches!(state, ExecutionStatus::Unknown(_))
+}
+
+impl Drop for ExecutionHandle {
+    fn drop(&mut self) {
+        if !self.cancel_on_drop || self.finished.load(Ordering::Relaxed) {
+            return;
+        }
+        let Ok(runtime) = tokio::runtime::Handle::try_current() else {
+            warn!(
+                "cannot cancel execution {} without a tokio runtime",
+                self.execution_id
+            );
+            return;
+        };
+        let client = self.client.clone();
+        let execution_id = std::mem::take(&mut self.execution_id);
+        info!("cancelling dropped execution {}", execution_id);
+        runtime.spawn(async move {
+            if let Err(err) = client.cancel_execution(&execution_id).await {
+                warn!("failed to cancel dropped execution {execution_id}: {err}");
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use std::collections::HashMap;
+    use std::time::Duration;
+    use wiremock::matchers::{method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    async fn mount_execution(
+        server: &MockServer,
+        execution_id: &str,
+        state: &str,
+        cancellations: u64,
+    ) {
+        Mock::given(method("POST"))
+            .and(path("/query/71/execute"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "execution_id": execution_id,
+                "state": "QUERY_STATE_PENDING"
+            })))
+            .mount(server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path(format!("/execution/{execution_id}/status")))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "execution_id": execution_id,
+                "query_id": 71,
+                "state": state,
+                "submitted_at": "2022-01-01T01:02:03.123Z"
+            })))
+            .mount(server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path(format!("/execution/{execution_id}/cancel")))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({"success": true})))
+            .expect(cancellations)
+            .mount(server)
+            .await;
+    }
+
+    #[tokio::test]
+    async fn cancels_when_dropped() {
+        let server = MockServer::start().await;
+        mount_execution(&server, "jerb", "QUERY_STATE_EXECUTING", 1).await;
+        let dune = DuneClient::builder("Baloney")
+            .base_url(&server.uri())
+            .build();
+
+        let execution = dune
+            .submit(71, None, None)
+            .await
+            .unwrap()
+            .cancel_on_drop(true);
+        assert_eq!(execution.execution_id(), "jerb");
+        assert!(!execution.status().await.unwrap().state.is_terminal());
+        // Abandoning the wait leaves the execution running until the handle is dropped.
+        let wait = tokio::time::timeout(
+            Duration::from_millis(20),
+            execution.wait::<HashMap<String, String>>(None),
+        );
+        assert!(wait.await.is_err());
+        drop(execution);
+        tokio::time::sleep(Duration::from_millis(50)).await;
+    }
+
+    #[tokio::test]
+    async fn cancels_unknown_states_when_dropped() {
+        let server = MockServer::start().await;
+        mount_execution(&server, "jerb", "QUERY_STATE_NEW", 1).await;
+        let dune = DuneClient::builder("Baloney")
+            .base_url(&server.uri())
+            .build();
+
+        let execution = dune
+            .submit(71, None, None)
+            .await
+            .unwrap()
+            .cancel_on_drop(true);
+        let error = execution
+            .wait::<HashMap<String, String>>(None)
+            .await
+            .unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::UnknownState);
+        assert!(execution.status().await.unwrap().state.is_terminal());
+        drop(execution);
+        tokio::time::sleep(Duration::from_millis(50)).await;
+    }
+
+    #[tokio::test]
+    async fn detached_executions_keep_running() {
+        let server = MockServer::start().await;
+        mount_execution(&server, "jerb", "QUERY_STATE_EXECUTING", 0).await;
+        let dune = DuneClient::builder("Baloney")
+            .base_url(&server.uri())
+            .build();
+
+        let execution = dune
+            .submit(71, None, None)
+            .await
+            .unwrap()
+            .cancel_on_drop(true);
+        assert_eq!(execution.detach(), "jerb");
+        // Handles without cancel_on_drop never cancel either.
+        drop(ExecutionHandle::new(dune, "jerb"));
+        tokio::time::sleep(Duration::from_millis(50)).await;
+    }
+}
@@ -1,10 +1,30 @@
+/// Executing queries over date ranges window by window.
+pub mod backfill;
+/// Options and outcome of executing many queries concurrently.
+pub mod batch;
+/// On-disk cache of query results.
+pub mod cache;
 /// DuneClient structure and all API route implementations.
 pub mod client;
 /// DuneRequestError (encapsulating all errors that could arise within network requests and result parsing)
 pub mod error;
+/// Handles to submitted executions (optionally cancelled when dropped).
+pub mod execution;
 /// Content related to Query Parameters.
 pub mod parameters;
 /// Utility Methods (primarily for date parsing)
 pub mod parse_utils;
+/// Request payloads for creating and updating saved queries.
+pub mod query;
+/// Client-side rate limiting of requests.
+pub mod rate_limit;
+/// Options controlling how long and how often `refresh` waits for executions.
+pub mod refresh;
 /// Data models representing response types for all client methods.
 pub mod response;
+/// Retry policy for transient request failures.
+pub mod retry;
+/// Synchronization of local `.sql` files with saved Dune queries.
+pub mod sync;
+/// Schema types and request payloads for uploading data into Dune tables.
+pub mod table;
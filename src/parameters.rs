@@ -1,10 +1,14 @@
 use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
 
 /// Dune supports 4 different parameter types enumerated here:
 /// In end, all parameters are passed to
 /// Dune via the API as JSON strings.
-#[derive(Debug, PartialEq)]
-enum ParameterType {
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ParameterType {
     /// A.k.a. string (used for transaction hashes and evm addresses, etc.)
     Text,
     /// Encapsulates all numerical types (integer and float).
@@ -12,18 +16,56 @@ enum ParameterType {
     /// A.k.a. List or Dropdown of text.
     Enum,
     /// Dune Date strings take the form `YYYY-MM-DD hh:mm:ss`
+    #[serde(rename = "datetime")]
     Date,
 }
 
-#[derive(Debug, PartialEq)]
+impl FromStr for ParameterType {
+    type Err = String;
+
+    /// Parses the type names used by Dune (`text`, `number`, `enum` and `datetime`).
+    fn from_str(input: &str) -> Result<ParameterType, Self::Err> {
+        match input {
+            "text" => Ok(ParameterType::Text),
+            "number" => Ok(ParameterType::Number),
+            "enum" => Ok(ParameterType::Enum),
+            "datetime" => Ok(ParameterType::Date),
+            other => Err(format!("Parse Error {other}")),
+        }
+    }
+}
+
+impl fmt::Display for ParameterType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            ParameterType::Text => "text",
+            ParameterType::Number => "number",
+            ParameterType::Enum => "enum",
+            ParameterType::Date => "datetime",
+        };
+        write!(f, "{name}")
+    }
+}
+
+/// Used both as a parameter value when executing queries
+/// and as a typed parameter definition when creating or updating queries.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct Parameter {
     /// Parameter Name.
     pub key: String,
-    /// Currently unused type field
-    /// (will become relevant when API supports `upsert_query`)
-    ptype: ParameterType,
+    /// Parameter type (only relevant for query definitions,
+    /// executions pass all values as strings).
+    #[serde(rename = "type")]
+    pub ptype: ParameterType,
     /// String representation of parameter's value
     pub value: String,
+    /// Dropdown options of List/Enum type parameter definitions.
+    #[serde(
+        rename = "enumOptions",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub enum_options: Option<Vec<String>>,
 }
 
 impl Parameter {
@@ -35,6 +77,7 @@ impl Parameter {
             // Dune date precision is to the second.
             // YYYY-MM-DD HH:MM:SS
             value: value.to_string()[..19].parse().unwrap(),
+            enum_options: None,
         }
     }
 
@@ -44,6 +87,7 @@ impl Parameter {
             key: String::from(name),
             ptype: ParameterType::Text,
             value: String::from(value),
+            enum_options: None,
         }
     }
 
@@ -53,6 +97,7 @@ impl Parameter {
             key: String::from(name),
             ptype: ParameterType::Number,
             value: String::from(value),
+            enum_options: None,
         }
     }
 
@@ -62,8 +107,26 @@ impl Parameter {
             key: String::from(name),
             ptype: ParameterType::Enum,
             value: String::from(value),
+            enum_options: None,
         }
     }
+
+    /// Constructor of List/Enum type Parameter definition (i.e. with dropdown `options`).
+    pub fn list_with_options(name: &str, value: &str, options: &[&str]) -> Self {
+        Parameter {
+            enum_options: Some(options.iter().map(|o| o.to_string()).collect()),
+            ..Parameter::list(name, value)
+        }
+    }
+}
+
+/// Query engine size used for an execution.
+/// Large executions cost more credits but are faster (and able to handle heavier queries).
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Performance {
+    Medium,
+    Large,
 }
 
 #[cfg(test)]
@@ -79,6 +142,7 @@ mod tests {
                 key: "MyText".to_string(),
                 ptype: ParameterType::Text,
                 value: "Hello!".to_string(),
+                enum_options: None,
             }
         );
         assert_eq!(
@@ -87,6 +151,7 @@ mod tests {
                 key: "MyEnum".to_string(),
                 ptype: ParameterType::Enum,
                 value: "Item 1".to_string(),
+                enum_options: None,
             }
         );
         assert_eq!(
@@ -95,6 +160,7 @@ mod tests {
                 key: "MyNumber".to_string(),
                 ptype: ParameterType::Number,
                 value: "3.14159".to_string(),
+                enum_options: None,
             }
         );
         let date_str = "2022-01-01T01:02:03.123Z";
@@ -104,16 +170,69 @@ mod tests {
                 key: "MyDate".to_string(),
                 ptype: ParameterType::Date,
                 value: "2022-01-01 01:02:03".to_string(),
+                enum_options: None,
             }
         )
     }
 
+    #[test]
+    fn parameter_definition_serialization() {
+        assert_eq!(
+            serde_json::to_value(Parameter::date(
+                "MyDate",
+                date_parse("2022-01-01T01:02:03.123Z").unwrap()
+            ))
+            .unwrap(),
+            serde_json::json!({"key": "MyDate", "type": "datetime", "value": "2022-01-01 01:02:03"})
+        );
+        let enum_param = Parameter::list_with_options("MyEnum", "A", &["A", "B"]);
+        let json = serde_json::json!({
+            "key": "MyEnum",
+            "type": "enum",
+            "value": "A",
+            "enumOptions": ["A", "B"]
+        });
+        assert_eq!(serde_json::to_value(&enum_param).unwrap(), json);
+        assert_eq!(
+            serde_json::from_value::<Parameter>(json).unwrap(),
+            enum_param
+        );
+    }
+
+    #[test]
+    fn parameter_type_from_str() {
+        for ptype in [
+            ParameterType::Text,
+            ParameterType::Number,
+            ParameterType::Enum,
+            ParameterType::Date,
+        ] {
+            assert_eq!(ParameterType::from_str(&ptype.to_string()), Ok(ptype));
+        }
+        assert_eq!(
+            ParameterType::from_str("date"),
+            Err(String::from("Parse Error date"))
+        );
+    }
+
+    #[test]
+    fn performance_serialization() {
+        assert_eq!(
+            serde_json::to_string(&Performance::Medium).unwrap(),
+            "\"medium\""
+        );
+        assert_eq!(
+            serde_json::to_string(&Performance::Large).unwrap(),
+            "\"large\""
+        );
+    }
+
     #[test]
     fn derived_debug() {
         assert_eq!(format!("{:?}", ParameterType::Date), "Date");
         assert_eq!(
             format!("{:?}", Parameter::number("MyNumber", "3.14159")),
-            "Parameter { key: \"MyNumber\", ptype: Number, value: \"3.14159\" }"
+            "Parameter { key: \"MyNumber\", ptype: Number, value: \"3.14159\", enum_options: None }"
         );
     }
 }
@@ -50,15 +50,19 @@ where
     }
 }
 
+/// Parses numbers which Dune returns as strings (e.g. large or high precision decimals).
+/// Plain numbers are accepted too, as CSV deserialization infers numeric fields.
 pub fn f64_from_str<'de, D>(deserializer: D) -> Result<f64, D::Error>
 where
     D: Deserializer<'de>,
 {
     let value: Value = Deserialize::deserialize(deserializer)?;
-    if let Value::String(s) = value {
-        s.parse().map_err(de::Error::custom)
-    } else {
-        Err(de::Error::custom("Expected a string"))
+    match value {
+        Value::String(s) => s.parse().map_err(de::Error::custom),
+        Value::Number(n) => n
+            .as_f64()
+            .ok_or_else(|| de::Error::custom("Expected a finite number")),
+        _ => Err(de::Error::custom("Expected a string")),
     }
 }
 
@@ -84,6 +88,19 @@ mod tests {
         )
     }
 
+    #[test]
+    fn f64_from_str_or_number() {
+        #[derive(Deserialize)]
+        struct Row {
+            #[serde(deserialize_with = "f64_from_str")]
+            value: f64,
+        }
+        let parse = |json: &str| serde_json::from_str::<Row>(json).map(|row| row.value);
+        assert_eq!(parse(r#"{"value": "3.5"}"#).unwrap(), 3.5);
+        assert_eq!(parse(r#"{"value": 3.5}"#).unwrap(), 3.5);
+        assert!(parse(r#"{"value": true}"#).is_err());
+    }
+
     #[test]
     fn dune_date_without_microseconds() {
         let date_str = "2022-05-04 00:00:00";
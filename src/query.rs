@@ -0,0 +1,74 @@
+use crate::parameters::Parameter;
+use serde::Serialize;
+
+/// Payload of `DuneClient::create_query`.
+/// Only `name` and `query_sql` are required, e.g.
+/// ```
+/// use duners::query::CreateQuery;
+///
+/// let query = CreateQuery {
+///     name: "Latest Block".to_string(),
+///     query_sql: "SELECT max(number) FROM ethereum.blocks".to_string(),
+///     ..Default::default()
+/// };
+/// ```
+#[derive(Serialize, Debug, Default, Clone, PartialEq)]
+pub struct CreateQuery {
+    pub name: String,
+    pub query_sql: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    /// Typed parameter definitions (their values act as defaults).
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub parameters: Vec<Parameter>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    pub is_private: bool,
+}
+
+/// Payload of `DuneClient::update_query`.
+/// Fields left as `None` remain unchanged on Dune.
+#[derive(Serialize, Debug, Default, Clone, PartialEq)]
+pub struct UpdateQuery {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub query_sql: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub parameters: Option<Vec<Parameter>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tags: Option<Vec<String>>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn skips_unset_fields() {
+        let create = CreateQuery {
+            name: "Blocks".to_string(),
+            query_sql: "SELECT 1".to_string(),
+            ..Default::default()
+        };
+        assert_eq!(
+            serde_json::to_value(create).unwrap(),
+            json!({"name": "Blocks", "query_sql": "SELECT 1", "is_private": false})
+        );
+        let update = UpdateQuery {
+            query_sql: Some("SELECT 2".to_string()),
+            parameters: Some(vec![Parameter::number("Width", "3")]),
+            ..Default::default()
+        };
+        assert_eq!(
+            serde_json::to_value(update).unwrap(),
+            json!({
+                "query_sql": "SELECT 2",
+                "parameters": [{"key": "Width", "type": "number", "value": "3"}]
+            })
+        );
+    }
+}
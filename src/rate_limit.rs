@@ -0,0 +1,124 @@
+use log::debug;
+use reqwest::Method;
+use std::sync::{Arc, Mutex};
+use tokio::time::{sleep, Duration, Instant};
+
+/// Client-side token bucket rate limiter throttling requests before they reach Dune.
+///
+/// Executions (and all other non-GET requests) draw from a different bucket than reads
+/// (status polls, results), mirroring Dune's separate rate limits for both kinds of endpoints.
+/// Each bucket refills continuously at its per-minute rate and allows bursts of
+/// up to ten seconds worth of requests.
+///
+/// Clones share their buckets, so a single limiter can throttle all tasks
+/// (and clients) of a process together.
+///
+/// # Examples
+/// ```
+/// use duners::client::DuneClient;
+/// use duners::rate_limit::RateLimiter;
+///
+/// let limiter = RateLimiter::new(15, 40);
+/// let dune = DuneClient::builder("my-api-key")
+///     .rate_limiter(limiter.clone())
+///     .build();
+/// ```
+#[derive(Clone, Debug)]
+pub struct RateLimiter {
+    execute: Arc<Mutex<TokenBucket>>,
+    read: Arc<Mutex<TokenBucket>>,
+}
+
+impl RateLimiter {
+    /// Limiter allowing `execute_per_minute` executions (and other writes)
+    /// and `read_per_minute` reads per minute.
+    pub fn new(execute_per_minute: u32, read_per_minute: u32) -> Self {
+        RateLimiter {
+            execute: Arc::new(Mutex::new(TokenBucket::new(execute_per_minute))),
+            read: Arc::new(Mutex::new(TokenBucket::new(read_per_minute))),
+        }
+    }
+
+    /// Waits until a request with the given HTTP `method` may be sent.
+    pub(crate) async fn acquire(&self, method: &Method) {
+        let bucket = match *method {
+            Method::GET => &self.read,
+            _ => &self.execute,
+        };
+        let delay = bucket.lock().unwrap().reserve(Instant::now());
+        if !delay.is_zero() {
+            debug!("rate limiter delays {} request by {:?}", method, delay);
+            sleep(delay).await;
+        }
+    }
+}
+
+#[derive(Debug)]
+struct TokenBucket {
+    capacity: f64,
+    /// Available tokens, negative when future requests have already been reserved.
+    tokens: f64,
+    per_second: f64,
+    updated: Instant,
+}
+
+impl TokenBucket {
+    fn new(per_minute: u32) -> Self {
+        let per_minute = per_minute.max(1) as f64;
+        let capacity = (per_minute / 6.0).max(1.0);
+        TokenBucket {
+            capacity,
+            tokens: capacity,
+            per_second: per_minute / 60.0,
+            updated: Instant::now(),
+        }
+    }
+
+    /// Takes a token, returning how long to wait until it becomes available.
+    /// Reserving (rather than polling for) tokens serves waiting requests in order.
+    fn reserve(&mut self, now: Instant) -> Duration {
+        let elapsed = now.saturating_duration_since(self.updated).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * self.per_second).min(self.capacity);
+        self.updated = now;
+        self.tokens -= 1.0;
+        match self.tokens < 0.0 {
+            true => Duration::from_secs_f64(-self.tokens / self.per_second),
+            false => Duration::ZERO,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn token_bucket() {
+        // 60 per minute: bursts of 10, then one per second.
+        let mut bucket = TokenBucket::new(60);
+        let start = bucket.updated;
+        for _ in 0..10 {
+            assert_eq!(bucket.reserve(start), Duration::ZERO);
+        }
+        assert_eq!(bucket.reserve(start), Duration::from_secs(1));
+        assert_eq!(bucket.reserve(start), Duration::from_secs(2));
+        // Refills over time (but never beyond capacity).
+        let later = start + Duration::from_secs(60);
+        assert_eq!(bucket.reserve(later), Duration::ZERO);
+        assert!(bucket.tokens <= bucket.capacity);
+    }
+
+    #[tokio::test]
+    async fn separate_buckets() {
+        let limiter = RateLimiter::new(1, 600);
+        let shared = limiter.clone();
+        shared.acquire(&Method::POST).await;
+        let started = Instant::now();
+        // Reads are unaffected by the exhausted execute bucket.
+        for _ in 0..10 {
+            limiter.acquire(&Method::GET).await;
+        }
+        assert!(started.elapsed() < Duration::from_secs(1));
+        assert!(limiter.execute.lock().unwrap().tokens < 0.5);
+    }
+}
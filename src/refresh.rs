@@ -0,0 +1,395 @@
+use crate::error::DuneRequestError;
+use crate::response::{ExecutionResponse, ExecutionStatus, GetStatusResponse};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::fmt;
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::broadcast;
+
+/// Controls how [refresh](crate::client::DuneClient::refresh) waits for an execution.
+///
+/// When either time limit is exceeded, the execution is cancelled
+/// and a [Timeout](crate::error::DuneRequestError::Timeout) error is returned.
+///
+/// # Examples
+/// ```
+/// use duners::refresh::{PollStrategy, RefreshOptions};
+/// use std::time::Duration;
+///
+/// let options = RefreshOptions {
+///     max_wall_time: Some(Duration::from_secs(10 * 60)),
+///     max_queue_wait: Some(Duration::from_secs(60)),
+///     poll: PollStrategy::Adaptive {
+///         min: Duration::from_secs(1),
+///         max: Duration::from_secs(60),
+///     },
+///     ..Default::default()
+/// };
+/// ```
+#[derive(Clone)]
+pub struct RefreshOptions {
+    /// Longest time to wait for the execution to finish (unlimited when `None`).
+    pub max_wall_time: Option<Duration>,
+    /// Longest time the execution may stay pending in Dune's queue (unlimited when `None`).
+    pub max_queue_wait: Option<Duration>,
+    /// Determines the time between two execution status checks (every 5 seconds by default).
+    /// Polling too frequently could result in rate limiting (i.e. Too Many Requests).
+    pub poll: PollStrategy,
+    /// Notified about the progress of the execution.
+    pub observer: Option<Arc<dyn RefreshObserver>>,
+}
+
+impl fmt::Debug for RefreshOptions {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RefreshOptions")
+            .field("max_wall_time", &self.max_wall_time)
+            .field("max_queue_wait", &self.max_queue_wait)
+            .field("poll", &self.poll)
+            .field("observer", &self.observer.as_ref().map(|_| ".."))
+            .finish()
+    }
+}
+
+impl Default for RefreshOptions {
+    /// No time limits, checking the execution status every 5 seconds.
+    fn default() -> Self {
+        RefreshOptions {
+            max_wall_time: None,
+            max_queue_wait: None,
+            poll: PollStrategy::Fixed(Duration::from_secs(5)),
+            observer: None,
+        }
+    }
+}
+
+impl RefreshOptions {
+    /// Default options polling every `poll_interval`.
+    pub fn polling_every(poll_interval: Duration) -> Self {
+        RefreshOptions {
+            poll: PollStrategy::Fixed(poll_interval),
+            ..Default::default()
+        }
+    }
+
+    /// Remaining time allowed after `elapsed`, given whether the execution is still `queued`
+    /// (`None` when unlimited, zero once exceeded).
+    pub(crate) fn remaining(&self, elapsed: Duration, queued: bool) -> Option<Duration> {
+        let queue_limit = self.max_queue_wait.filter(|_| queued);
+        [self.max_wall_time, queue_limit]
+            .into_iter()
+            .flatten()
+            .min()
+            .map(|limit| limit.saturating_sub(elapsed))
+    }
+}
+
+/// Hooks into the progress of [refresh](crate::client::DuneClient::refresh)
+/// (resp. [await_execution](crate::client::DuneClient::await_execution)),
+/// e.g. to persist execution IDs as soon as they exist. All methods default to doing nothing.
+///
+/// # Examples
+/// ```
+/// use duners::refresh::{RefreshObserver, RefreshOptions};
+/// use duners::response::ExecutionResponse;
+/// use std::sync::Arc;
+///
+/// struct Journal;
+///
+/// impl RefreshObserver for Journal {
+///     fn on_submitted(&self, execution: &ExecutionResponse) {
+///         println!("started execution {}", execution.execution_id);
+///     }
+/// }
+///
+/// let options = RefreshOptions {
+///     observer: Some(Arc::new(Journal)),
+///     ..Default::default()
+/// };
+/// ```
+pub trait RefreshObserver: Send + Sync {
+    /// A new execution was submitted.
+    fn on_submitted(&self, _execution: &ExecutionResponse) {}
+
+    /// The execution status was checked.
+    fn on_status(&self, _status: &GetStatusResponse) {}
+
+    /// The execution completed (its results are fetched next).
+    fn on_completed(&self, _status: &GetStatusResponse) {}
+
+    /// Waiting for (or fetching the results of) execution `execution_id` failed.
+    fn on_failed(&self, _execution_id: &str, _error: &DuneRequestError) {}
+}
+
+/// Determines the delay between two execution status checks.
+#[derive(Clone, Debug, PartialEq)]
+pub enum PollStrategy {
+    /// Constant delay.
+    Fixed(Duration),
+    /// Delay starting at `initial`, growing by `factor` after every check up to `max`.
+    Exponential {
+        initial: Duration,
+        factor: f64,
+        max: Duration,
+    },
+    /// Delay (between `min` and `max`) derived from the execution's queue position
+    /// and the execution time of previous runs of the same query (as observed by the client):
+    /// executions expected to finish soon are checked frequently, long ones rarely.
+    Adaptive { min: Duration, max: Duration },
+}
+
+impl PollStrategy {
+    /// Delay before the next status check, given the number of `checks` so far,
+    /// the latest `status`, the time `elapsed` since the execution started
+    /// (resp. was submitted while still pending)
+    /// and the `expected` execution time (if known from previous runs).
+    pub(crate) fn delay(
+        &self,
+        checks: u32,
+        status: &GetStatusResponse,
+        elapsed: Duration,
+        expected: Option<Duration>,
+    ) -> Duration {
+        match *self {
+            PollStrategy::Fixed(delay) => delay,
+            PollStrategy::Exponential {
+                initial,
+                factor,
+                max,
+            } => {
+                // Scaled as f64, as the Duration could overflow before being capped at `max`.
+                let secs = initial.as_secs_f64() * factor.max(1.0).powi(checks.min(64) as i32);
+                Duration::try_from_secs_f64(secs.min(max.as_secs_f64())).unwrap_or(max)
+            }
+            PollStrategy::Adaptive { min, max } => {
+                let delay = match (&status.state, status.queue_position, expected) {
+                    // Every execution ahead in the queue has to start first.
+                    (ExecutionStatus::Pending, Some(position), _) => {
+                        min.saturating_mul(position.saturating_add(1))
+                    }
+                    // Check again halfway through the expected remaining time.
+                    (ExecutionStatus::Executing, _, Some(expected)) => {
+                        expected.saturating_sub(elapsed) / 2
+                    }
+                    // Without any hints, the longer an execution already took,
+                    // the longer it probably takes to finish.
+                    _ => elapsed / 4,
+                };
+                delay.clamp(min, max.max(min))
+            }
+        }
+    }
+}
+
+/// Execution times of previous runs per query, shared between clones of a client.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct ExecutionHistory(Arc<Mutex<HashMap<u32, Duration>>>);
+
+impl ExecutionHistory {
+    /// Remembers the execution time of the latest completed run of `query_id`.
+    pub(crate) fn record(&self, query_id: u32, execution_time: Duration) {
+        self.0.lock().unwrap().insert(query_id, execution_time);
+    }
+
+    /// Execution time of the latest completed run of `query_id` (if any).
+    pub(crate) fn expected(&self, query_id: u32) -> Option<Duration> {
+        self.0.lock().unwrap().get(&query_id).copied()
+    }
+}
+
+/// Outcome of a refresh, as handed to concurrent identical refreshes.
+type SharedResult = Result<Arc<Value>, DuneRequestError>;
+
+/// Refreshes in progress per key (cf. [query_key](crate::cache::ResultCache::query_key)),
+/// shared between clones of a client so that identical refreshes execute only once.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct InFlight(Arc<Mutex<HashMap<String, broadcast::Sender<SharedResult>>>>);
+
+/// Part a refresh plays in [InFlight::run](InFlight::run).
+enum Role {
+    /// Awaits the result of the refresh in progress.
+    Wait(broadcast::Receiver<SharedResult>),
+    /// Refreshes, sharing the result with whoever joins meanwhile.
+    Lead,
+    /// Refreshes on its own (next to the refresh in progress).
+    Alone,
+}
+
+/// Unregisters the refresh under `key` when finished (or abandoned).
+struct Leader<'a> {
+    in_flight: &'a InFlight,
+    key: &'a str,
+}
+
+impl Leader<'_> {
+    /// Unregisters the finished refresh, returning the channel to its waiters.
+    fn finish(self) -> Option<broadcast::Sender<SharedResult>> {
+        let sender = self.in_flight.0.lock().unwrap().remove(self.key);
+        std::mem::forget(self);
+        sender
+    }
+}
+
+impl Drop for Leader<'_> {
+    fn drop(&mut self) {
+        self.in_flight.0.lock().unwrap().remove(self.key);
+    }
+}
+
+impl InFlight {
+    /// Runs `refresh` unless another refresh under `key` is in progress and may be `join`ed,
+    /// in which case that one's raw results (resp. error) are awaited instead.
+    /// When the refresh in progress is abandoned (i.e. its caller dropped),
+    /// one of its waiters takes over.
+    pub(crate) async fn run(
+        &self,
+        key: &str,
+        join: bool,
+        refresh: impl Future<Output = Result<Value, DuneRequestError>>,
+    ) -> Result<Arc<Value>, DuneRequestError> {
+        loop {
+            let role = {
+                let mut in_flight = self.0.lock().unwrap();
+                match in_flight.get(key) {
+                    Some(sender) if join => Role::Wait(sender.subscribe()),
+                    Some(_) => Role::Alone,
+                    None => {
+                        in_flight.insert(key.to_string(), broadcast::channel(1).0);
+                        Role::Lead
+                    }
+                }
+            };
+            match role {
+                Role::Wait(mut receiver) => match receiver.recv().await {
+                    Ok(result) => return result,
+                    Err(_) => continue,
+                },
+                Role::Alone => return refresh.await.map(Arc::new),
+                Role::Lead => {}
+            }
+            let leader = Leader {
+                in_flight: self,
+                key,
+            };
+            let result = refresh.await.map(Arc::new);
+            // Nobody subscribes once unregistered, so all waiters receive the result.
+            if let Some(sender) = leader.finish() {
+                // Fails only when nobody is waiting.
+                let _ = sender.send(result.clone());
+            }
+            return result;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn status(state: &str, queue_position: Option<u32>) -> GetStatusResponse {
+        serde_json::from_value(json!({
+            "execution_id": "jerb",
+            "query_id": 71,
+            "state": state,
+            "submitted_at": "2022-01-01T01:02:03.123Z",
+            "queue_position": queue_position
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn remaining_time() {
+        let options = RefreshOptions {
+            max_wall_time: Some(Duration::from_secs(60)),
+            max_queue_wait: Some(Duration::from_secs(10)),
+            ..Default::default()
+        };
+        let elapsed = Duration::from_secs(4);
+        assert_eq!(
+            options.remaining(elapsed, true),
+            Some(Duration::from_secs(6))
+        );
+        assert_eq!(
+            options.remaining(elapsed, false),
+            Some(Duration::from_secs(56))
+        );
+        assert_eq!(
+            options.remaining(Duration::from_secs(90), false),
+            Some(Duration::ZERO)
+        );
+        assert_eq!(RefreshOptions::default().remaining(elapsed, true), None);
+    }
+
+    #[test]
+    fn poll_strategies() {
+        let secs = Duration::from_secs;
+        let executing = status("QUERY_STATE_EXECUTING", None);
+        assert_eq!(
+            PollStrategy::Fixed(secs(3)).delay(7, &executing, secs(100), None),
+            secs(3)
+        );
+
+        let exponential = PollStrategy::Exponential {
+            initial: secs(1),
+            factor: 2.0,
+            max: secs(10),
+        };
+        let delays = (0..5)
+            .map(|checks| exponential.delay(checks, &executing, secs(0), None))
+            .collect::<Vec<_>>();
+        assert_eq!(delays, [1, 2, 4, 8, 10].map(secs).to_vec());
+        assert_eq!(exponential.delay(1000, &executing, secs(0), None), secs(10));
+        let infinite = PollStrategy::Exponential {
+            initial: secs(1),
+            factor: f64::INFINITY,
+            max: secs(10),
+        };
+        assert_eq!(infinite.delay(1, &executing, secs(0), None), secs(10));
+
+        let adaptive = PollStrategy::Adaptive {
+            min: secs(1),
+            max: secs(60),
+        };
+        let queued = status("QUERY_STATE_PENDING", Some(4));
+        assert_eq!(adaptive.delay(0, &queued, secs(0), None), secs(5));
+        // Known to take about 2 minutes
+        assert_eq!(
+            adaptive.delay(0, &executing, secs(20), Some(secs(120))),
+            secs(50)
+        );
+        // Overdue
+        assert_eq!(
+            adaptive.delay(0, &executing, secs(200), Some(secs(120))),
+            secs(1)
+        );
+        // Unknown
+        assert_eq!(adaptive.delay(0, &executing, secs(8), None), secs(2));
+        assert_eq!(adaptive.delay(0, &executing, secs(3600), None), secs(60));
+    }
+
+    #[test]
+    fn execution_history() {
+        let history = ExecutionHistory::default();
+        history.clone().record(71, Duration::from_millis(1500));
+        assert_eq!(history.expected(71), Some(Duration::from_millis(1500)));
+        assert_eq!(history.expected(72), None);
+    }
+
+    #[tokio::test]
+    async fn abandoned_refresh_taken_over() {
+        let in_flight = InFlight::default();
+        let mut abandoned = Box::pin(in_flight.run("key", true, std::future::pending()));
+        assert!(futures::poll!(&mut abandoned).is_pending());
+
+        let waiting = in_flight.run("key", true, async { Ok(json!(1)) });
+        let abandon = async {
+            tokio::task::yield_now().await;
+            drop(abandoned);
+        };
+        let (_, result) = tokio::join!(abandon, waiting);
+        assert_eq!(*result.unwrap(), json!(1));
+        assert!(in_flight.0.lock().unwrap().is_empty());
+    }
+}
@@ -1,8 +1,12 @@
+use crate::parameters::Parameter;
 use crate::parse_utils::{datetime_from_str, optional_datetime_from_str};
-use chrono::{DateTime, Utc};
+use crate::table::{Column, ColumnType};
+use chrono::{DateTime, TimeDelta, Utc};
 use serde::Deserialize;
 use serde_with::DeserializeFromStr;
+use std::convert::Infallible;
 use std::str::FromStr;
+use std::time::Duration;
 
 /// Returned from successful call to `DuneClient::execute_query`
 #[derive(Deserialize, Debug)]
@@ -15,42 +19,60 @@ pub struct ExecutionResponse {
 /// Most states are self-explanatory.
 /// Failure can occur if query takes too long (30 minutes) to execute.
 /// Pending state also comes along with a "queue position"
-#[derive(DeserializeFromStr, Debug, PartialEq)]
+#[derive(DeserializeFromStr, Clone, Debug, PartialEq)]
 pub enum ExecutionStatus {
     Complete,
+    /// Completed, but the results were truncated (e.g. for exceeding the maximum result size).
+    CompletedPartial,
     Executing,
     Pending,
     Cancelled,
     Failed,
+    /// The results of the execution are no longer stored.
+    Expired,
+    /// Any state not (yet) known to this library.
+    Unknown(String),
 }
 
 impl FromStr for ExecutionStatus {
-    type Err = String;
+    type Err = Infallible;
 
     fn from_str(input: &str) -> Result<ExecutionStatus, Self::Err> {
-        match input {
-            "QUERY_STATE_COMPLETED" => Ok(ExecutionStatus::Complete),
-            "QUERY_STATE_EXECUTING" => Ok(ExecutionStatus::Executing),
-            "QUERY_STATE_PENDING" => Ok(ExecutionStatus::Pending),
-            "QUERY_STATE_CANCELLED" => Ok(ExecutionStatus::Cancelled),
-            "QUERY_STATE_FAILED" => Ok(ExecutionStatus::Failed),
-            other => Err(format!("Parse Error {other}")),
-        }
+        Ok(match input {
+            "QUERY_STATE_COMPLETED" => ExecutionStatus::Complete,
+            "QUERY_STATE_COMPLETED_PARTIAL" => ExecutionStatus::CompletedPartial,
+            "QUERY_STATE_EXECUTING" => ExecutionStatus::Executing,
+            "QUERY_STATE_PENDING" => ExecutionStatus::Pending,
+            "QUERY_STATE_CANCELLED" => ExecutionStatus::Cancelled,
+            "QUERY_STATE_FAILED" => ExecutionStatus::Failed,
+            "QUERY_STATE_EXPIRED" => ExecutionStatus::Expired,
+            other => ExecutionStatus::Unknown(other.to_string()),
+        })
     }
 }
 
 impl ExecutionStatus {
     /// utility method for terminal query execution status.
-    /// The three terminal states are complete, cancelled and failed.
+    /// The terminal states are complete (also partially), cancelled, failed and expired.
+    /// Unknown states are considered terminal as well, so that waiting for an execution
+    /// never hangs on states introduced by Dune after this library was released.
     pub fn is_terminal(&self) -> bool {
         match self {
             ExecutionStatus::Complete => true,
+            ExecutionStatus::CompletedPartial => true,
             ExecutionStatus::Cancelled => true,
             ExecutionStatus::Failed => true,
+            ExecutionStatus::Expired => true,
             ExecutionStatus::Executing => false,
             ExecutionStatus::Pending => false,
+            ExecutionStatus::Unknown(_) => true,
         }
     }
+
+    /// Whether the execution completed with truncated results.
+    pub fn is_partial(&self) -> bool {
+        *self == ExecutionStatus::CompletedPartial
+    }
 }
 
 /// Returned from call to `DuneClient::cancel_execution`
@@ -79,6 +101,28 @@ pub struct ResultMetaData {
     pub execution_time_millis: u32,
 }
 
+impl ResultMetaData {
+    /// Table schema equivalent to the result columns (None when Dune did not report column types).
+    /// All columns are nullable, as results carry no nullability information.
+    pub fn schema(&self) -> Option<Vec<Column>> {
+        let column_types = self.column_types.as_ref()?;
+        let schema = self
+            .column_names
+            .iter()
+            .zip(column_types)
+            .map(|(name, ctype)| {
+                let Ok(ctype) = ctype.parse::<ColumnType>();
+                Column {
+                    name: name.clone(),
+                    ctype,
+                    nullable: true,
+                }
+            })
+            .collect();
+        Some(schema)
+    }
+}
+
 /// Nested inside [GetStatusResponse](GetStatusResponse)
 /// and [GetResultResponse](GetResultResponse).
 /// Contains several UTC timestamps related to the query execution.
@@ -117,6 +161,74 @@ pub struct GetStatusResponse {
     pub queue_position: Option<u32>,
     /// This field will be non-empty once query execution has completed.
     pub result_metadata: Option<ResultMetaData>,
+    /// Reason of failure for failed executions.
+    #[serde(default)]
+    pub error: Option<ExecutionError>,
+    /// Credits consumed by the execution (once reported by Dune).
+    #[serde(default)]
+    pub execution_cost_credits: Option<f64>,
+}
+
+/// Details of a failed execution (contained in [GetStatusResponse](GetStatusResponse)),
+/// e.g. a syntax error in the query.
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+pub struct ExecutionError {
+    /// Category of failure, e.g. `FAILED_TYPE_EXECUTION_FAILED`.
+    #[serde(rename = "type")]
+    pub error_type: String,
+    pub message: String,
+    /// Location of the error within the query (if applicable).
+    #[serde(default)]
+    pub metadata: Option<ExecutionErrorMetadata>,
+}
+
+impl ExecutionError {
+    /// Whether the execution ran into Dune's time or memory limits
+    /// (so that it may succeed when processing less data), as opposed to e.g. SQL errors.
+    ///
+    /// Recognizes limit related error types (e.g. `EXCEEDED_TIME_LIMIT`, `..._TIMEOUT`)
+    /// and messages like "Query exceeded per-node memory limit of 40GB", matching whole words
+    /// only, so that identifiers such as `memory_used` quoted by SQL errors are not mistaken for limits.
+    pub fn is_resource_limit(&self) -> bool {
+        let error_type = self.error_type.to_uppercase();
+        if [
+            "EXCEEDED_",
+            "TIMEOUT",
+            "TIMED_OUT",
+            "OUT_OF_MEMORY",
+            "INSUFFICIENT_RESOURCES",
+        ]
+        .iter()
+        .any(|hint| error_type.contains(hint))
+        {
+            return true;
+        }
+        let message = self.message.to_lowercase();
+        let words: Vec<&str> = message
+            .split(|c: char| !(c.is_alphanumeric() || c == '_'))
+            .filter(|word| !word.is_empty())
+            .collect();
+        let exceeded_limit = words
+            .iter()
+            .position(|word| *word == "exceeded")
+            .is_some_and(|at| words[at..].iter().any(|w| *w == "limit" || *w == "limits"));
+        let phrases: [&[&str]; 3] = [
+            &["timed", "out"],
+            &["out", "of", "memory"],
+            &["insufficient", "resources"],
+        ];
+        exceeded_limit
+            || phrases
+                .iter()
+                .any(|phrase| words.windows(phrase.len()).any(|w| w == *phrase))
+    }
+}
+
+/// Position of an [ExecutionError](ExecutionError) within the query SQL.
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+pub struct ExecutionErrorMetadata {
+    pub line: Option<u32>,
+    pub column: Option<u32>,
 }
 
 /// Contains the query results along with some additional metadata.
@@ -144,6 +256,15 @@ pub struct GetResultResponse<T> {
     #[serde(flatten)]
     pub times: ExecutionTimes,
     pub result: ExecutionResult<T>,
+    /// Offset of the next page of results (absent on the last page).
+    #[serde(default)]
+    pub next_offset: Option<u64>,
+    /// URI of the next page of results (absent on the last page).
+    #[serde(default)]
+    pub next_uri: Option<String>,
+    /// Credits consumed by the execution (if reported by Dune).
+    #[serde(default)]
+    pub execution_cost_credits: Option<f64>,
 }
 
 impl<T> GetResultResponse<T> {
@@ -151,6 +272,124 @@ impl<T> GetResultResponse<T> {
     pub fn get_rows(self) -> Vec<T> {
         self.result.rows
     }
+
+    /// Whether these results are truncated (i.e. not all rows of the query are contained),
+    /// cf. [CompletedPartial](ExecutionStatus::CompletedPartial).
+    pub fn is_partial(&self) -> bool {
+        self.state.is_partial()
+    }
+
+    /// Whether these results were produced more than `max_age` ago
+    /// (results of executions which never completed are always stale).
+    pub fn is_stale(&self, max_age: Duration) -> bool {
+        match (self.times.execution_ended_at, TimeDelta::from_std(max_age)) {
+            (Some(ended_at), Ok(max_age)) => Utc::now() - ended_at > max_age,
+            (Some(_), Err(_)) => false,
+            (None, _) => true,
+        }
+    }
+}
+
+/// Returned from calls to `DuneClient::create_query`, `DuneClient::update_query`,
+/// and the archive/privacy routes of saved queries.
+#[derive(Deserialize, Debug)]
+pub struct QueryIdResponse {
+    pub query_id: u32,
+}
+
+/// Saved query as returned by `DuneClient::get_query`.
+#[derive(Deserialize, Debug)]
+pub struct Query {
+    pub query_id: u32,
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    #[serde(default)]
+    pub version: Option<u32>,
+    /// Typed parameter definitions (with their default values).
+    #[serde(default)]
+    pub parameters: Vec<Parameter>,
+    #[serde(default)]
+    pub query_engine: Option<String>,
+    pub query_sql: String,
+    #[serde(default)]
+    pub is_private: bool,
+    #[serde(default)]
+    pub is_archived: bool,
+    #[serde(default)]
+    pub is_unsaved: bool,
+    #[serde(default)]
+    pub owner: Option<String>,
+}
+
+/// Returned from call to `DuneClient::upload_csv`
+#[derive(Deserialize, Debug)]
+pub struct UploadCsvResponse {
+    pub success: bool,
+    #[serde(default)]
+    pub namespace: Option<String>,
+    #[serde(default)]
+    pub table_name: Option<String>,
+    #[serde(default)]
+    pub full_name: Option<String>,
+}
+
+/// Returned from call to `DuneClient::create_table`
+#[derive(Deserialize, Debug)]
+pub struct CreateTableResponse {
+    pub namespace: String,
+    pub table_name: String,
+    /// Name to be used in queries (i.e. `dune.<namespace>.<table_name>`).
+    pub full_name: String,
+    #[serde(default)]
+    pub example_query: Option<String>,
+    /// true when the table existed before (in which case it was left untouched).
+    #[serde(default)]
+    pub already_existed: bool,
+    #[serde(default)]
+    pub message: Option<String>,
+}
+
+/// Returned from call to `DuneClient::insert`
+#[derive(Deserialize, Debug)]
+pub struct InsertResponse {
+    pub rows_written: u64,
+    pub bytes_written: u64,
+    #[serde(default)]
+    pub name: Option<String>,
+}
+
+/// Returned from calls to `DuneClient::clear_table` and `DuneClient::delete_table`
+#[derive(Deserialize, Debug)]
+pub struct TableMessageResponse {
+    pub message: String,
+}
+
+/// Entry of [ListTablesResponse](ListTablesResponse).
+#[derive(Deserialize, Debug)]
+pub struct TableInfo {
+    pub full_name: String,
+    #[serde(default)]
+    pub is_private: bool,
+    #[serde(default)]
+    pub table_size_bytes: Option<u64>,
+    #[serde(default)]
+    pub created_at: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub updated_at: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub columns: Vec<Column>,
+}
+
+/// Returned from call to `DuneClient::list_tables`
+#[derive(Deserialize, Debug)]
+pub struct ListTablesResponse {
+    pub tables: Vec<TableInfo>,
+    /// Offset of the next page of tables (absent on the last page).
+    #[serde(default)]
+    pub next_offset: Option<u64>,
 }
 
 #[cfg(test)]
@@ -161,7 +400,7 @@ mod tests {
     fn status_from_str() {
         assert_eq!(
             ExecutionStatus::from_str("invalid"),
-            Err(String::from("Parse Error invalid"))
+            Ok(ExecutionStatus::Unknown(String::from("invalid")))
         );
         assert_eq!(
             ExecutionStatus::from_str("QUERY_STATE_COMPLETED"),
@@ -183,6 +422,14 @@ mod tests {
             ExecutionStatus::from_str("QUERY_STATE_FAILED"),
             Ok(ExecutionStatus::Failed)
         );
+        assert_eq!(
+            ExecutionStatus::from_str("QUERY_STATE_EXPIRED"),
+            Ok(ExecutionStatus::Expired)
+        );
+        assert_eq!(
+            ExecutionStatus::from_str("QUERY_STATE_COMPLETED_PARTIAL"),
+            Ok(ExecutionStatus::CompletedPartial)
+        );
     }
 
     #[test]
@@ -190,10 +437,109 @@ mod tests {
         assert!(ExecutionStatus::Complete.is_terminal());
         assert!(ExecutionStatus::Cancelled.is_terminal());
         assert!(ExecutionStatus::Failed.is_terminal());
+        assert!(ExecutionStatus::Expired.is_terminal());
+        assert!(ExecutionStatus::CompletedPartial.is_terminal());
 
         assert!(!ExecutionStatus::Pending.is_terminal());
         assert!(!ExecutionStatus::Executing.is_terminal());
+        assert!(ExecutionStatus::Unknown("QUERY_STATE_NEW".to_string()).is_terminal());
+
+        assert!(ExecutionStatus::CompletedPartial.is_partial());
+        assert!(!ExecutionStatus::Complete.is_partial());
     }
+
+    #[test]
+    fn failed_status() {
+        let status: GetStatusResponse = serde_json::from_value(serde_json::json!({
+            "execution_id": "01HKZJ2683PHF9Q9PHHQ8FW4Q1",
+            "query_id": 1234,
+            "state": "QUERY_STATE_FAILED",
+            "submitted_at": "2024-12-20T11:04:18.724658237Z",
+            "execution_ended_at": "2024-12-20T11:04:19.10279Z",
+            "error": {
+                "type": "FAILED_TYPE_EXECUTION_FAILED",
+                "message": "line 1:8: Column 'x' cannot be resolved",
+                "metadata": {"line": 1, "column": 8}
+            }
+        }))
+        .unwrap();
+        assert_eq!(
+            status.error,
+            Some(ExecutionError {
+                error_type: "FAILED_TYPE_EXECUTION_FAILED".to_string(),
+                message: "line 1:8: Column 'x' cannot be resolved".to_string(),
+                metadata: Some(ExecutionErrorMetadata {
+                    line: Some(1),
+                    column: Some(8)
+                }),
+            })
+        );
+    }
+
+    #[test]
+    fn metadata_schema() {
+        let metadata = ResultMetaData {
+            column_names: vec!["symbol".to_string(), "max_price".to_string()],
+            column_types: Some(vec!["varchar".to_string(), "double".to_string()]),
+            row_count: None,
+            result_set_bytes: 0,
+            total_result_set_bytes: None,
+            total_row_count: 0,
+            datapoint_count: 0,
+            pending_time_millis: None,
+            execution_time_millis: 0,
+        };
+        assert_eq!(
+            metadata.schema(),
+            Some(vec![
+                Column::new("symbol", ColumnType::Varchar, true),
+                Column::new("max_price", ColumnType::Double, true),
+            ])
+        );
+    }
+
+    #[test]
+    fn stale_results() {
+        let results = |execution_ended_at: Option<DateTime<Utc>>| GetResultResponse::<u8> {
+            execution_id: "jerb".to_string(),
+            query_id: 71,
+            is_execution_finished: None,
+            state: ExecutionStatus::Complete,
+            times: ExecutionTimes {
+                submitted_at: Default::default(),
+                expires_at: None,
+                execution_started_at: None,
+                execution_ended_at,
+                cancelled_at: None,
+            },
+            result: ExecutionResult {
+                rows: vec![],
+                metadata: ResultMetaData {
+                    column_names: vec![],
+                    column_types: None,
+                    row_count: None,
+                    result_set_bytes: 0,
+                    total_result_set_bytes: None,
+                    total_row_count: 0,
+                    datapoint_count: 0,
+                    pending_time_millis: None,
+                    execution_time_millis: 0,
+                },
+            },
+            next_offset: None,
+            next_uri: None,
+            execution_cost_credits: None,
+        };
+        let hour = Duration::from_secs(3600);
+        let two_hours_ago = Utc::now() - TimeDelta::hours(2);
+        assert!(results(None).is_stale(hour));
+        assert!(results(Some(two_hours_ago)).is_stale(hour));
+        assert!(!results(Some(two_hours_ago)).is_stale(3 * hour));
+        assert!(!results(Some(two_hours_ago)).is_stale(Duration::MAX));
+        // Representable as TimeDelta, but not when added to a date
+        assert!(!results(Some(two_hours_ago)).is_stale(Duration::from_secs(u64::MAX / 4_000)));
+    }
+
     #[test]
     fn derive_debug() {
         assert_eq!(
@@ -239,6 +585,8 @@ mod tests {
                         pending_time_millis: None,
                         execution_time_millis: 0,
                     }),
+                    error: None,
+                    execution_cost_credits: Some(0.5),
                 }
             ),
             "GetStatusResponse { \
@@ -264,7 +612,9 @@ mod tests {
                         pending_time_millis: None, \
                         execution_time_millis: 0 \
                 }\
-             ) }",
+             ), \
+             error: None, \
+             execution_cost_credits: Some(0.5) }",
         );
         assert_eq!(
             format!(
@@ -295,6 +645,9 @@ mod tests {
                             execution_time_millis: 0,
                         }
                     },
+                    next_offset: None,
+                    next_uri: None,
+                    execution_cost_credits: None,
                 }
             ),
             "GetResultResponse { \
@@ -322,7 +675,10 @@ mod tests {
                         pending_time_millis: None, \
                         execution_time_millis: 0 \
                     } \
-                } \
+                }, \
+                next_offset: None, \
+                next_uri: None, \
+                execution_cost_credits: None \
             }",
         );
     }
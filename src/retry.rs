@@ -0,0 +1,188 @@
+use crate::error::{DuneRequestError, ErrorKind};
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+use std::time::Duration;
+
+/// Determines whether (and after which delay) failed requests are sent again.
+///
+/// Delays grow exponentially from `base_delay` (doubling with every attempt) up to `max_delay`.
+/// When Dune answers with `429 Too Many Requests` and a `Retry-After` header,
+/// the requested delay is waited instead, unless it exceeds `max_delay`,
+/// in which case the `RateLimited` error is returned rather than retrying too early.
+///
+/// Routes creating something on every call (executions, saved queries, inserted rows)
+/// are not idempotent and hence only retried when `retry_non_idempotent` is set.
+///
+/// # Examples
+/// ```
+/// use duners::client::DuneClient;
+/// use duners::retry::RetryPolicy;
+/// use std::time::Duration;
+///
+/// let dune = DuneClient::builder("my-api-key")
+///     .retry_policy(RetryPolicy {
+///         max_attempts: 5,
+///         max_delay: Duration::from_secs(60),
+///         ..Default::default()
+///     })
+///     .build();
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct RetryPolicy {
+    /// Total number of attempts per request (including the first one), 1 disables retries.
+    pub max_attempts: u32,
+    /// Delay before the first retry.
+    pub base_delay: Duration,
+    /// Upper bound of the delay between attempts
+    /// (longer delays requested via `Retry-After` are not retried).
+    pub max_delay: Duration,
+    /// Randomizes delays (between half and the full delay)
+    /// so that concurrent clients do not retry in lockstep.
+    pub jitter: bool,
+    /// Errors worth another attempt.
+    pub retry_on: Vec<ErrorKind>,
+    /// Also retry non-idempotent requests (e.g. query executions),
+    /// at the risk of running them more than once.
+    pub retry_non_idempotent: bool,
+}
+
+impl Default for RetryPolicy {
+    /// Up to 4 attempts, retrying rate limits, server errors and connection failures.
+    fn default() -> Self {
+        RetryPolicy {
+            max_attempts: 4,
+            base_delay: Duration::from_millis(500),
+            max_delay: Duration::from_secs(30),
+            jitter: true,
+            retry_on: vec![
+                ErrorKind::RateLimited,
+                ErrorKind::Server,
+                ErrorKind::Transport,
+            ],
+            retry_non_idempotent: false,
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Policy sending every request exactly once (the client's default).
+    pub fn none() -> Self {
+        RetryPolicy {
+            max_attempts: 1,
+            ..Default::default()
+        }
+    }
+
+    /// Delay (before jitter) after the given number of failed `attempts`.
+    pub fn backoff(&self, attempts: u32) -> Duration {
+        let factor = 2u32.saturating_pow(attempts.saturating_sub(1));
+        self.base_delay.saturating_mul(factor).min(self.max_delay)
+    }
+
+    /// Delay before the next attempt, or `None` when `err` is final
+    /// (after `attempts` attempts of a request with the given idempotency).
+    pub(crate) fn retry_delay(
+        &self,
+        err: &DuneRequestError,
+        attempts: u32,
+        idempotent: bool,
+    ) -> Option<Duration> {
+        if attempts >= self.max_attempts
+            || !(idempotent || self.retry_non_idempotent)
+            || !self.retry_on.contains(&err.kind())
+        {
+            return None;
+        }
+        match err {
+            // Malformed requests fail the same way every time.
+            DuneRequestError::Transport(err) if err.is_builder() => None,
+            DuneRequestError::RateLimited {
+                retry_after: Some(delay),
+                ..
+            } => (*delay <= self.max_delay).then_some(*delay),
+            _ => {
+                let delay = self.backoff(attempts);
+                Some(match self.jitter {
+                    true => delay.mul_f64(0.5 + random_fraction() / 2.0),
+                    false => delay,
+                })
+            }
+        }
+    }
+}
+
+/// Uniformly distributed value in `[0, 1)`, seeded by the standard library's per-hasher random keys.
+fn random_fraction() -> f64 {
+    let hash = RandomState::new().build_hasher().finish();
+    (hash >> 11) as f64 / (1u64 << 53) as f64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reqwest::StatusCode;
+
+    fn server_error() -> DuneRequestError {
+        DuneRequestError::from_status(StatusCode::BAD_GATEWAY, None, "")
+    }
+
+    #[test]
+    fn exponential_backoff() {
+        let policy = RetryPolicy {
+            base_delay: Duration::from_secs(1),
+            max_delay: Duration::from_secs(5),
+            ..Default::default()
+        };
+        let delays = (1..=5).map(|n| policy.backoff(n)).collect::<Vec<_>>();
+        assert_eq!(delays, [1, 2, 4, 5, 5].map(Duration::from_secs).to_vec());
+    }
+
+    #[test]
+    fn retry_decisions() {
+        let policy = RetryPolicy {
+            jitter: false,
+            ..Default::default()
+        };
+        assert_eq!(
+            policy.retry_delay(&server_error(), 2, true),
+            Some(Duration::from_secs(1))
+        );
+        // Out of attempts
+        assert_eq!(policy.retry_delay(&server_error(), 4, true), None);
+        // Not idempotent
+        assert_eq!(policy.retry_delay(&server_error(), 1, false), None);
+        // Not retryable
+        let not_found = DuneRequestError::NotFound("Query not found".to_string());
+        assert_eq!(policy.retry_delay(&not_found, 1, true), None);
+        // Retry-After takes precedence (up to max_delay, giving up beyond)
+        let throttled = DuneRequestError::RateLimited {
+            retry_after: Some(Duration::from_secs(12)),
+            message: String::new(),
+        };
+        assert_eq!(
+            policy.retry_delay(&throttled, 1, true),
+            Some(Duration::from_secs(12))
+        );
+        let stalling = DuneRequestError::RateLimited {
+            retry_after: Some(Duration::from_secs(24 * 3600)),
+            message: String::new(),
+        };
+        assert_eq!(policy.retry_delay(&stalling, 1, true), None);
+        assert_eq!(RetryPolicy::none().retry_delay(&throttled, 1, true), None);
+
+        let opted_in = RetryPolicy {
+            retry_non_idempotent: true,
+            ..policy
+        };
+        assert!(opted_in.retry_delay(&server_error(), 1, false).is_some());
+    }
+
+    #[test]
+    fn jitter_stays_within_bounds() {
+        let policy = RetryPolicy::default();
+        for _ in 0..100 {
+            let delay = policy.retry_delay(&server_error(), 3, true).unwrap();
+            assert!(delay >= Duration::from_secs(1) && delay <= Duration::from_secs(2));
+        }
+    }
+}
@@ -0,0 +1,492 @@
+//! Keeps saved Dune queries in lockstep with a directory of `.sql` files.
+//!
+//! Each file starts with a front-matter header written as SQL comments:
+//! ```sql
+//! -- ---
+//! -- query_id: 3238189
+//! -- name: Block Range
+//! -- param: Start number 5
+//! -- param: Chain enum ethereum | ethereum, gnosis
+//! -- ---
+//! SELECT number, time FROM ethereum.blocks WHERE number >= {{Start}}
+//! ```
+//! Parameters are declared as `<key> <type> <default value>`
+//! (enum options may follow the value, separated by `|`).
+//! Files without `query_id` are created on Dune and the new ID is written back into the header.
+use crate::client::DuneClient;
+use crate::error::DuneRequestError;
+use crate::parameters::{Parameter, ParameterType};
+use crate::query::{CreateQuery, UpdateQuery};
+use crate::response::Query;
+use log::info;
+use std::error::Error;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Delimiter of the front-matter header.
+const FENCE: &str = "-- ---";
+
+/// A query definition read from a local `.sql` file.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LocalQuery {
+    pub path: PathBuf,
+    /// None for queries which do not exist on Dune yet.
+    pub query_id: Option<u32>,
+    pub name: String,
+    pub parameters: Vec<Parameter>,
+    pub query_sql: String,
+}
+
+/// Everything that can go wrong while planning or applying a sync.
+#[derive(Debug)]
+pub enum SyncError {
+    Io {
+        path: PathBuf,
+        error: std::io::Error,
+    },
+    /// The front-matter header of a file could not be parsed.
+    FrontMatter {
+        path: PathBuf,
+        message: String,
+    },
+    Request(DuneRequestError),
+}
+
+impl fmt::Display for SyncError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SyncError::Io { path, error } => write!(f, "{}: {error}", path.display()),
+            SyncError::FrontMatter { path, message } => {
+                write!(f, "{}: invalid front matter: {message}", path.display())
+            }
+            SyncError::Request(error) => write!(f, "{error}"),
+        }
+    }
+}
+
+impl Error for SyncError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            SyncError::Io { error, .. } => Some(error),
+            SyncError::FrontMatter { .. } => None,
+            SyncError::Request(error) => Some(error),
+        }
+    }
+}
+
+impl From<DuneRequestError> for SyncError {
+    fn from(value: DuneRequestError) -> Self {
+        SyncError::Request(value)
+    }
+}
+
+impl LocalQuery {
+    /// Reads and parses a single `.sql` file.
+    pub fn read(path: &Path) -> Result<LocalQuery, SyncError> {
+        let content = fs::read_to_string(path).map_err(|error| SyncError::Io {
+            path: path.to_path_buf(),
+            error,
+        })?;
+        LocalQuery::parse(path, &content).map_err(|message| SyncError::FrontMatter {
+            path: path.to_path_buf(),
+            message,
+        })
+    }
+
+    /// Parses file `content` (the `path` stem is used as name when the header has none).
+    pub fn parse(path: &Path, content: &str) -> Result<LocalQuery, String> {
+        let mut query = LocalQuery {
+            path: path.to_path_buf(),
+            query_id: None,
+            name: path
+                .file_stem()
+                .map(|stem| stem.to_string_lossy().to_string())
+                .unwrap_or_default(),
+            parameters: vec![],
+            query_sql: normalize(content),
+        };
+        // Lines including their `\n` (resp. `\r\n`), so that the header length is exact.
+        let mut lines = content.split_inclusive('\n');
+        let mut header_len = match lines.next() {
+            Some(line) if line.trim_end() == FENCE => line.len(),
+            _ => return Ok(query),
+        };
+        let mut closed = false;
+        for line in lines {
+            header_len += line.len();
+            let line = line.trim_end();
+            if line == FENCE {
+                closed = true;
+                break;
+            }
+            let entry = line
+                .strip_prefix("--")
+                .ok_or_else(|| format!("expected comment line, got `{line}`"))?;
+            let (key, value) = entry
+                .split_once(':')
+                .ok_or_else(|| format!("expected `key: value`, got `{line}`"))?;
+            let value = value.trim();
+            match key.trim() {
+                "query_id" => {
+                    query.query_id = Some(value.parse().map_err(|_| {
+                        format!("query_id must be an unsigned integer, got `{value}`")
+                    })?)
+                }
+                "name" => query.name = value.to_string(),
+                "param" => query.parameters.push(parse_parameter(value)?),
+                other => return Err(format!("unknown key `{other}`")),
+            }
+        }
+        if !closed {
+            return Err(format!("missing closing `{FENCE}`"));
+        }
+        query.query_sql = normalize(&content[header_len..]);
+        Ok(query)
+    }
+
+    /// Renders the query back into the file format (header followed by SQL).
+    pub fn render(&self) -> String {
+        let mut header = vec![FENCE.to_string()];
+        if let Some(query_id) = self.query_id {
+            header.push(format!("-- query_id: {query_id}"));
+        }
+        header.push(format!("-- name: {}", self.name));
+        for p in &self.parameters {
+            let mut line = format!("-- param: {} {} {}", p.key, p.ptype, p.value);
+            if let Some(options) = &p.enum_options {
+                line.push_str(&format!(" | {}", options.join(", ")));
+            }
+            header.push(line);
+        }
+        header.push(FENCE.to_string());
+        format!("{}\n{}\n", header.join("\n"), self.query_sql)
+    }
+
+    /// Fields which differ from the `remote` query (None when in sync).
+    fn changes(&self, remote: &Query) -> Option<UpdateQuery> {
+        let sorted = |params: &[Parameter]| {
+            let mut params = params.to_vec();
+            params.sort_by(|a, b| a.key.cmp(&b.key));
+            params
+        };
+        let update = UpdateQuery {
+            name: (self.name != remote.name).then(|| self.name.clone()),
+            query_sql: (self.query_sql != normalize(&remote.query_sql))
+                .then(|| self.query_sql.clone()),
+            parameters: (sorted(&self.parameters) != sorted(&remote.parameters))
+                .then(|| self.parameters.clone()),
+            ..Default::default()
+        };
+        (update != UpdateQuery::default()).then_some(update)
+    }
+}
+
+/// Trimmed `sql` with `\n` line endings (as stored by Dune, whatever the local checkout uses).
+fn normalize(sql: &str) -> String {
+    sql.trim().replace("\r\n", "\n")
+}
+
+/// Parses `<key> <type> <value>[ | <option>, <option>, ...]`.
+fn parse_parameter(declaration: &str) -> Result<Parameter, String> {
+    let mut parts = declaration.splitn(3, ' ');
+    let (Some(key), Some(ptype)) = (parts.next(), parts.next()) else {
+        return Err(format!(
+            "expected `<key> <type> <value>`, got `{declaration}`"
+        ));
+    };
+    let ptype = ptype.parse::<ParameterType>()?;
+    let rest = parts.next().unwrap_or("").trim();
+    let (value, enum_options) = match (&ptype, rest.split_once('|')) {
+        (ParameterType::Enum, Some((value, options))) => (
+            value.trim(),
+            Some(options.split(',').map(|o| o.trim().to_string()).collect()),
+        ),
+        _ => (rest, None),
+    };
+    Ok(Parameter {
+        key: key.to_string(),
+        ptype,
+        value: value.to_string(),
+        enum_options,
+    })
+}
+
+/// What a sync would do for a single file.
+#[derive(Debug, Clone, PartialEq)]
+pub enum SyncAction {
+    Create(LocalQuery),
+    Update {
+        query_id: u32,
+        local: LocalQuery,
+        changes: UpdateQuery,
+    },
+    Unchanged {
+        query_id: u32,
+        local: LocalQuery,
+    },
+}
+
+/// Sync actions for all `.sql` files of a directory (sorted by path).
+/// Its `Display` implementation serves as dry-run output.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SyncPlan {
+    pub actions: Vec<SyncAction>,
+}
+
+impl SyncPlan {
+    /// Diffs all `.sql` files in `dir` against their remote counterparts.
+    pub async fn build(dune: &DuneClient, dir: &Path) -> Result<SyncPlan, SyncError> {
+        let io_error = |error| SyncError::Io {
+            path: dir.to_path_buf(),
+            error,
+        };
+        let mut paths = fs::read_dir(dir)
+            .map_err(io_error)?
+            .map(|entry| entry.map(|e| e.path()))
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(io_error)?;
+        paths.retain(|path| path.extension().is_some_and(|ext| ext == "sql"));
+        paths.sort();
+
+        let mut actions = vec![];
+        for path in paths {
+            let local = LocalQuery::read(&path)?;
+            let action = match local.query_id {
+                None => SyncAction::Create(local),
+                Some(query_id) => {
+                    let remote = dune.get_query(query_id).await?;
+                    match local.changes(&remote) {
+                        Some(changes) => SyncAction::Update {
+                            query_id,
+                            local,
+                            changes,
+                        },
+                        None => SyncAction::Unchanged { query_id, local },
+                    }
+                }
+            };
+            actions.push(action);
+        }
+        Ok(SyncPlan { actions })
+    }
+
+    /// Whether applying the plan would not change anything.
+    pub fn is_empty(&self) -> bool {
+        self.actions
+            .iter()
+            .all(|action| matches!(action, SyncAction::Unchanged { .. }))
+    }
+
+    /// Creates and updates the remote queries.
+    /// IDs of newly created queries are written back into the front matter of their files.
+    pub async fn apply(&self, dune: &DuneClient) -> Result<(), SyncError> {
+        for action in &self.actions {
+            match action {
+                SyncAction::Create(local) => {
+                    let created = dune
+                        .create_query(&CreateQuery {
+                            name: local.name.clone(),
+                            query_sql: local.query_sql.clone(),
+                            parameters: local.parameters.clone(),
+                            ..Default::default()
+                        })
+                        .await?;
+                    info!(
+                        "created query {} from {}",
+                        created.query_id,
+                        local.path.display()
+                    );
+                    let synced = LocalQuery {
+                        query_id: Some(created.query_id),
+                        ..local.clone()
+                    };
+                    fs::write(&local.path, synced.render()).map_err(|error| SyncError::Io {
+                        path: local.path.clone(),
+                        error,
+                    })?;
+                }
+                SyncAction::Update {
+                    query_id,
+                    local,
+                    changes,
+                } => {
+                    dune.update_query(*query_id, changes).await?;
+                    info!("updated query {} from {}", query_id, local.path.display());
+                }
+                SyncAction::Unchanged { .. } => {}
+            }
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for SyncPlan {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for action in &self.actions {
+            match action {
+                SyncAction::Create(local) => {
+                    writeln!(f, "create    {} ({})", local.path.display(), local.name)?
+                }
+                SyncAction::Update {
+                    query_id,
+                    local,
+                    changes,
+                } => {
+                    let fields = [
+                        changes.name.as_ref().map(|_| "name"),
+                        changes.query_sql.as_ref().map(|_| "sql"),
+                        changes.parameters.as_ref().map(|_| "parameters"),
+                    ];
+                    let fields = fields.into_iter().flatten().collect::<Vec<_>>();
+                    writeln!(
+                        f,
+                        "update    {} (#{query_id}: {})",
+                        local.path.display(),
+                        fields.join(", ")
+                    )?
+                }
+                SyncAction::Unchanged { query_id, local } => {
+                    writeln!(f, "unchanged {} (#{query_id})", local.path.display())?
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use wiremock::matchers::{body_json, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    const FILE: &str = "-- ---
+-- query_id: 3238189
+-- name: Block Range
+-- param: Start number 5
+-- param: Chain enum ethereum | ethereum, gnosis
+-- ---
+SELECT number, time FROM ethereum.blocks WHERE number >= {{Start}}
+";
+
+    #[test]
+    fn parse_and_render() {
+        let query = LocalQuery::parse(Path::new("q/blocks.sql"), FILE).unwrap();
+        assert_eq!(
+            query,
+            LocalQuery {
+                path: PathBuf::from("q/blocks.sql"),
+                query_id: Some(3238189),
+                name: "Block Range".to_string(),
+                parameters: vec![
+                    Parameter::number("Start", "5"),
+                    Parameter::list_with_options("Chain", "ethereum", &["ethereum", "gnosis"]),
+                ],
+                query_sql: "SELECT number, time FROM ethereum.blocks WHERE number >= {{Start}}"
+                    .to_string(),
+            }
+        );
+        assert_eq!(query.render(), FILE);
+    }
+
+    #[test]
+    fn parse_crlf() {
+        let crlf = FILE.replace('\n', "\r\n");
+        let query = LocalQuery::parse(Path::new("q/blocks.sql"), &crlf).unwrap();
+        assert_eq!(
+            query,
+            LocalQuery::parse(Path::new("q/blocks.sql"), FILE).unwrap()
+        );
+        // In sync with the unchanged query on Dune
+        let remote: Query = serde_json::from_value(serde_json::json!({
+            "query_id": 3238189,
+            "name": "Block Range",
+            "query_sql": "SELECT number, time FROM ethereum.blocks WHERE number >= {{Start}}",
+            "parameters": [
+                {"key": "Chain", "type": "enum", "value": "ethereum", "enumOptions": ["ethereum", "gnosis"]},
+                {"key": "Start", "type": "number", "value": "5"}
+            ]
+        }))
+        .unwrap();
+        assert_eq!(query.changes(&remote), None);
+    }
+
+    #[test]
+    fn parse_without_front_matter() {
+        let query = LocalQuery::parse(Path::new("q/blocks.sql"), "SELECT 1\n").unwrap();
+        assert_eq!(query.query_id, None);
+        assert_eq!(query.name, "blocks");
+        assert_eq!(query.query_sql, "SELECT 1");
+
+        let invalid = "-- ---\n-- query_id: twelve\n-- ---\nSELECT 1";
+        assert_eq!(
+            LocalQuery::parse(Path::new("q/blocks.sql"), invalid),
+            Err("query_id must be an unsigned integer, got `twelve`".to_string())
+        );
+        let unclosed = "-- ---\n-- name: Blocks\nSELECT 1";
+        assert!(LocalQuery::parse(Path::new("q/blocks.sql"), unclosed).is_err());
+    }
+
+    #[tokio::test]
+    async fn plan_and_apply() {
+        let dir = std::env::temp_dir().join(format!("duners-sync-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("a_new.sql"), "SELECT 1\n").unwrap();
+        fs::write(dir.join("b_blocks.sql"), FILE).unwrap();
+        fs::write(dir.join("notes.md"), "not a query").unwrap();
+
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/query/3238189"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "query_id": 3238189,
+                "name": "Block Range",
+                "query_sql": "SELECT number FROM ethereum.blocks",
+                "parameters": [
+                    {"key": "Chain", "type": "enum", "value": "ethereum", "enumOptions": ["ethereum", "gnosis"]},
+                    {"key": "Start", "type": "number", "value": "5"}
+                ]
+            })))
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/query"))
+            .and(body_json(
+                json!({"name": "a_new", "query_sql": "SELECT 1", "is_private": false}),
+            ))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({"query_id": 72})))
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("PATCH"))
+            .and(path("/query/3238189"))
+            .and(body_json(json!({
+                "query_sql": "SELECT number, time FROM ethereum.blocks WHERE number >= {{Start}}"
+            })))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({"query_id": 3238189})))
+            .expect(1)
+            .mount(&server)
+            .await;
+        let dune = DuneClient::builder("Baloney")
+            .base_url(&server.uri())
+            .build();
+
+        let plan = SyncPlan::build(&dune, &dir).await.unwrap();
+        assert_eq!(
+            plan.to_string(),
+            format!(
+                "create    {} (a_new)\nupdate    {} (#3238189: sql)\n",
+                dir.join("a_new.sql").display(),
+                dir.join("b_blocks.sql").display()
+            )
+        );
+        plan.apply(&dune).await.unwrap();
+        assert_eq!(
+            fs::read_to_string(dir.join("a_new.sql")).unwrap(),
+            "-- ---\n-- query_id: 72\n-- name: a_new\n-- ---\nSELECT 1\n"
+        );
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,276 @@
+use crate::error::DuneRequestError;
+use crate::response::InsertResponse;
+use serde::{Deserialize, Serialize};
+use serde_with::{DeserializeFromStr, SerializeDisplay};
+use std::convert::Infallible;
+use std::fmt;
+use std::str::FromStr;
+
+/// Column types supported by Dune tables.
+/// These mirror the types reported in `ResultMetaData.column_types`,
+/// where anything unsupported by uploads is kept as `Other`.
+#[derive(SerializeDisplay, DeserializeFromStr, Debug, Clone, PartialEq)]
+pub enum ColumnType {
+    Varchar,
+    Integer,
+    Bigint,
+    Double,
+    Boolean,
+    Timestamp,
+    Date,
+    Varbinary,
+    Uint256,
+    Int256,
+    Other(String),
+}
+
+impl FromStr for ColumnType {
+    type Err = Infallible;
+
+    fn from_str(input: &str) -> Result<ColumnType, Self::Err> {
+        let input = input.trim().to_lowercase();
+        Ok(match input.as_str() {
+            "varchar" => ColumnType::Varchar,
+            "integer" => ColumnType::Integer,
+            "bigint" => ColumnType::Bigint,
+            "double" => ColumnType::Double,
+            "boolean" => ColumnType::Boolean,
+            "date" => ColumnType::Date,
+            "varbinary" => ColumnType::Varbinary,
+            "uint256" => ColumnType::Uint256,
+            "int256" => ColumnType::Int256,
+            // Query results report precision and zone, e.g. `timestamp(3) with time zone`.
+            t if t.starts_with("timestamp") => ColumnType::Timestamp,
+            _ => ColumnType::Other(input),
+        })
+    }
+}
+
+impl fmt::Display for ColumnType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            ColumnType::Varchar => "varchar",
+            ColumnType::Integer => "integer",
+            ColumnType::Bigint => "bigint",
+            ColumnType::Double => "double",
+            ColumnType::Boolean => "boolean",
+            ColumnType::Timestamp => "timestamp",
+            ColumnType::Date => "date",
+            ColumnType::Varbinary => "varbinary",
+            ColumnType::Uint256 => "uint256",
+            ColumnType::Int256 => "int256",
+            ColumnType::Other(name) => name,
+        };
+        write!(f, "{name}")
+    }
+}
+
+/// Single column of a table schema.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Column {
+    pub name: String,
+    #[serde(rename = "type")]
+    pub ctype: ColumnType,
+    #[serde(default)]
+    pub nullable: bool,
+}
+
+impl Column {
+    pub fn new(name: &str, ctype: ColumnType, nullable: bool) -> Self {
+        Column {
+            name: name.to_string(),
+            ctype,
+            nullable,
+        }
+    }
+}
+
+/// Payload of `DuneClient::create_table`.
+#[derive(Serialize, Debug, Default, Clone, PartialEq)]
+pub struct CreateTable {
+    pub namespace: String,
+    pub table_name: String,
+    pub schema: Vec<Column>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    pub is_private: bool,
+}
+
+/// Payload of `DuneClient::upload_csv`
+/// (creates or replaces a table from CSV content in a single request).
+#[derive(Serialize, Debug, Default, Clone, PartialEq)]
+pub struct UploadCsv {
+    pub table_name: String,
+    /// CSV content including the header row.
+    pub data: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    pub is_private: bool,
+}
+
+/// Rows to be inserted into an existing table via `DuneClient::insert`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum TableData {
+    /// CSV content including the header row.
+    Csv(String),
+    /// Newline delimited JSON objects (one per row).
+    NdJson(String),
+}
+
+impl TableData {
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            TableData::Csv(_) => "text/csv",
+            TableData::NdJson(_) => "application/x-ndjson",
+        }
+    }
+
+    pub fn into_body(self) -> String {
+        match self {
+            TableData::Csv(body) | TableData::NdJson(body) => body,
+        }
+    }
+}
+
+/// Default upper bound on the size of a single insert request made by `DuneClient::insert_rows`.
+pub const MAX_INSERT_BATCH_BYTES: usize = 100 * 1024 * 1024;
+
+/// Implemented by row types to declare the schema of the table they are stored in,
+/// so that `DuneClient::insert_rows` can create the table when missing.
+///
+/// The schema is not inferred from the type: there is no derive, so the columns
+/// (and their Dune types) have to be listed by hand and kept in sync with the
+/// serialized fields. Rows copied from query results can reuse the columns Dune
+/// reports via [ResultMetaData::schema](crate::response::ResultMetaData::schema)
+/// and `DuneClient::create_table` instead.
+///
+/// # Examples
+/// ```
+/// use duners::{
+///     parse_utils::{datetime_from_str, f64_from_str},
+///     table::{Column, ColumnType, TableRow},
+/// };
+/// use chrono::{DateTime, Utc};
+/// use serde::{Deserialize, Serialize};
+///
+/// // The same struct can be read from `get_results` and written back with `insert_rows`.
+/// #[derive(Deserialize, Serialize)]
+/// struct Price {
+///     symbol: String,
+///     #[serde(deserialize_with = "f64_from_str")]
+///     price: f64,
+///     #[serde(deserialize_with = "datetime_from_str")]
+///     time: DateTime<Utc>,
+/// }
+///
+/// impl TableRow for Price {
+///     fn schema() -> Vec<Column> {
+///         vec![
+///             Column::new("symbol", ColumnType::Varchar, false),
+///             Column::new("price", ColumnType::Double, false),
+///             Column::new("time", ColumnType::Timestamp, false),
+///         ]
+///     }
+/// }
+/// ```
+pub trait TableRow {
+    /// Columns in the order and with the names the rows are serialized with.
+    fn schema() -> Vec<Column>;
+}
+
+/// NDJSON encoded chunk of rows (cf. [ndjson_batches](ndjson_batches)).
+#[derive(Debug, Clone, PartialEq)]
+pub struct NdJsonBatch {
+    pub rows: usize,
+    pub body: String,
+}
+
+/// Serializes `rows` into NDJSON batches of at most `max_bytes` each
+/// (a single row exceeding `max_bytes` is sent as a batch of its own).
+pub fn ndjson_batches<T: Serialize>(
+    rows: &[T],
+    max_bytes: usize,
+) -> Result<Vec<NdJsonBatch>, serde_json::Error> {
+    let mut batches = vec![];
+    let mut current = NdJsonBatch {
+        rows: 0,
+        body: String::new(),
+    };
+    for row in rows {
+        let mut line = serde_json::to_string(row)?;
+        line.push('\n');
+        if current.rows > 0 && current.body.len() + line.len() > max_bytes {
+            batches.push(std::mem::replace(
+                &mut current,
+                NdJsonBatch {
+                    rows: 0,
+                    body: String::new(),
+                },
+            ));
+        }
+        current.rows += 1;
+        current.body.push_str(&line);
+    }
+    if current.rows > 0 {
+        batches.push(current);
+    }
+    Ok(batches)
+}
+
+/// Result of inserting a single batch via `DuneClient::insert_rows`.
+#[derive(Debug)]
+pub struct BatchOutcome {
+    pub rows: usize,
+    pub bytes: usize,
+    pub result: Result<InsertResponse, DuneRequestError>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn column_type_from_str() {
+        assert_eq!(ColumnType::from_str("varchar"), Ok(ColumnType::Varchar));
+        assert_eq!(ColumnType::from_str("BIGINT"), Ok(ColumnType::Bigint));
+        assert_eq!(
+            ColumnType::from_str("timestamp(3) with time zone"),
+            Ok(ColumnType::Timestamp)
+        );
+        assert_eq!(
+            ColumnType::from_str("array(varchar)"),
+            Ok(ColumnType::Other("array(varchar)".to_string()))
+        );
+    }
+
+    #[test]
+    fn batches_by_size() {
+        let rows = [json!({"n": 1}), json!({"n": 22}), json!({"n": 3})];
+        let batches = ndjson_batches(&rows, 17).unwrap();
+        assert_eq!(
+            batches,
+            vec![
+                NdJsonBatch {
+                    rows: 2,
+                    body: "{\"n\":1}\n{\"n\":22}\n".to_string()
+                },
+                NdJsonBatch {
+                    rows: 1,
+                    body: "{\"n\":3}\n".to_string()
+                },
+            ]
+        );
+        // Oversized rows are not dropped.
+        assert_eq!(ndjson_batches(&rows, 1).unwrap().len(), 3);
+        assert!(ndjson_batches::<u8>(&[], 1).unwrap().is_empty());
+    }
+
+    #[test]
+    fn schema_serialization() {
+        let column = Column::new("price", ColumnType::Double, true);
+        let value = json!({"name": "price", "type": "double", "nullable": true});
+        assert_eq!(serde_json::to_value(&column).unwrap(), value);
+        assert_eq!(serde_json::from_value::<Column>(value).unwrap(), column);
+    }
+}
@@ -1,9 +1,10 @@
 use chrono::{DateTime, Utc};
 use duners::{
     client::DuneClient, parameters::Parameter, parse_utils::datetime_from_str,
-    parse_utils::f64_from_str,
+    parse_utils::f64_from_str, refresh::RefreshOptions,
 };
 use serde::Deserialize;
+use std::time::Duration;
 
 // User must declare the expected query return fields and types!
 #[derive(Deserialize, Debug, PartialEq)]
@@ -20,7 +21,7 @@ struct ResultStruct {
 async fn test_external_use() {
     let dune = DuneClient::from_env();
     let results = dune
-        .refresh::<ResultStruct>(1215383, None, None)
+        .refresh::<ResultStruct>(1215383, None, None, None)
         .await
         .unwrap();
     println!("{:?}", results.get_rows());
@@ -43,7 +44,8 @@ async fn test_blocks() {
                 Parameter::number("Start", &start.to_string()),
                 Parameter::number("Width", &(end - start).to_string()),
             ]),
-            Some(1),
+            None,
+            Some(RefreshOptions::polling_every(Duration::from_secs(1))),
         )
         .await
         .unwrap();